@@ -0,0 +1,166 @@
+use std::fmt::{Debug, Display};
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use serde::Serialize;
+use tracing::warn;
+
+use crate::ArchiveBot;
+
+/// Something the bot did that changed the workspace.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditAction {
+  /// Joined a channel.
+  Join,
+  /// Left a channel.
+  Leave,
+  /// Posted a message, including thread replies and direct messages.
+  Post,
+  /// Uploaded a file.
+  Upload,
+  /// Archived a channel.
+  Archive,
+}
+
+/// Record of a single action, and whether it succeeded.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AuditEntry {
+  /// When the action was taken, in seconds since the epoch.
+  pub timestamp: i64,
+  /// What was done.
+  pub action: AuditAction,
+  /// Channel acted on, or posted or uploaded to.
+  pub channel_id: String,
+  /// Why, e.g. the configuration or criteria that triggered it; `requested` if called directly.
+  pub reason: String,
+  /// Whether the platform accepted the call.
+  pub ok: bool,
+  /// The platform's error, if it didn't.
+  pub error: Option<String>,
+}
+
+/// Where to keep a record of every action. Dry runs aren't recorded, as nothing is done.
+pub trait AuditSink: Debug + Send + Sync {
+  /// Record an action. Failures to record should be handled by the sink; they don't stop the bot.
+  fn record(&self, entry: &AuditEntry);
+}
+
+/// Append-only JSON Lines audit log, one entry per line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AuditFile {
+  /// File to append to. Created if it doesn't exist.
+  pub path: &'static str,
+}
+
+impl AuditFile {
+  /// Append an entry to the file.
+  fn append(&self, entry: &AuditEntry) -> io::Result<()> {
+    let mut line = serde_json::to_string(entry)?;
+    line.push('\n');
+    // A single write per entry, so concurrent entries aren't interleaved.
+    OpenOptions::new().create(true).append(true).open(self.path)?.write_all(line.as_bytes())
+  }
+}
+
+impl AuditSink for AuditFile {
+  fn record(&self, entry: &AuditEntry) {
+    if let Err(e) = self.append(entry) {
+      warn!(path = self.path, error = %e, "Unable to write audit log");
+    }
+  }
+}
+
+/// Reason recorded for actions called directly, rather than by a run.
+pub(crate) const REQUESTED: &str = "requested";
+
+impl<P> ArchiveBot<P> {
+  /// Record an action in the audit log, if one is configured.
+  pub(crate) fn audit<T, E: Display>(&self, action: AuditAction, channel_id: &str, reason: &str, result: &Result<T, E>) {
+    if let Some(sink) = &self.audit_log {
+      sink.record(&AuditEntry {
+        timestamp: chrono::offset::Utc::now().timestamp(),
+        action,
+        channel_id: channel_id.to_string(),
+        reason: reason.to_string(),
+        ok: result.is_ok(),
+        error: result.as_ref().err().map(|e| e.to_string()),
+      });
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  #[cfg(feature = "unit")]
+  use super::*;
+  #[cfg(feature = "unit")]
+  use std::collections::HashMap;
+  #[cfg(feature = "unit")]
+  use std::sync::{Arc, Mutex};
+  #[cfg(feature = "unit")]
+  use crate::{Channel, FakePlatform, Message, Timestamp};
+
+  /// Keeps entries in memory.
+  #[cfg(feature = "unit")]
+  #[derive(Debug, Default)]
+  struct MemorySink(Arc<Mutex<Vec<AuditEntry>>>);
+
+  #[cfg(feature = "unit")]
+  impl AuditSink for MemorySink {
+    fn record(&self, entry: &AuditEntry) {
+      self.0.lock().unwrap().push(entry.clone());
+    }
+  }
+
+  /// Test that a run records what it did, and why.
+  #[tokio::test]
+  #[cfg(feature = "unit")]
+  async fn test_audit() {
+    let now = chrono::offset::Utc::now().timestamp();
+    let mut platform = FakePlatform::default();
+    platform.channels = vec![Channel {
+      id: "C01".to_string(),
+      name: "quiet".to_string(),
+      is_channel: true,
+      num_members: 10,
+      created: Some(Timestamp::new(now - 365 * 24 * 60 * 60)),
+      ..Channel::default()
+    }];
+    platform.histories = HashMap::from([("C01".to_string(), vec![Message {
+      event_type: "message".to_string(),
+      ts: Some(Timestamp::new(now - 100 * 24 * 60 * 60)),
+      ..Message::default()
+    }])]);
+    platform.failing = vec!["leave"];
+    let entries = Arc::new(Mutex::new(vec![]));
+    let bot = ArchiveBot {
+      notification_channel_id: "C00".to_string(),
+      leave_joined_channels: true,
+      shared_channels: crate::SharedChannelPolicy::Separate,
+      audit_log: Some(Box::new(MemorySink(entries.clone()))),
+      ..ArchiveBot::default()
+    }.with_platform(platform);
+
+    bot.run().await.unwrap();
+    bot.archive_channel(&bot.platform.channels[0]).await.unwrap();
+    let shared = Channel { id: "C02".to_string(), is_ext_shared: true, ..Channel::default() };
+    assert!(matches!(bot.archive_channel(&shared).await, Err(crate::ArchiveError::Shared)));
+
+    let entries = entries.lock().unwrap();
+    let summary: Vec<(AuditAction, &str, &str, bool)> = entries.iter()
+      .map(|e| (e.action, e.channel_id.as_str(), e.reason.as_str(), e.ok))
+      .collect();
+    assert_eq!(summary, vec![
+      (AuditAction::Join, "C01", "join_channels: all", true),
+      (AuditAction::Leave, "C01", "leave_joined_channels", false),
+      (AuditAction::Post, "C00", "update: 1 flagged channel: Stale (1)", true),
+      (AuditAction::Archive, "C01", "requested", true),
+      (AuditAction::Archive, "C02", "requested", false),
+    ]);
+    assert_eq!(bot.platform.calls().iter().filter(|c| matches!(c, crate::FakeCall::Archive(_))).count(), 1);
+    assert_eq!(entries[1].error.as_deref(), Some("leave"));
+
+    let line = serde_json::to_value(&entries[0]).unwrap();
+    assert_eq!(line["action"], "join");
+  }
+}
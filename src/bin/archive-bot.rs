@@ -0,0 +1,608 @@
+//! Archive Bot command-line interface.
+//!
+//! Configuration is read from flags, then environment variables, then a TOML config file.
+
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, BufWriter};
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use clap::{ArgAction, Parser, Subcommand};
+use serde::Deserialize;
+use archive_bot::{Action, ArchiveBot, ArchiveError, AuditFile, ChannelData, ChatPlatform, Daemon, DateFormat, Discord, EnterpriseGrid, ExportFormat, HtmlReport, JoinPolicy, Locale, Mattermost, Metrics, ReportMode, Schedule, SharedChannelPolicy, SortKey, Templates};
+
+/// Exit code when the command fails, e.g. an API call fails, a post or upload in a run fails, or a
+/// channel isn't found.
+const EXIT_FAILURE: u8 = 1;
+/// Exit code when the configuration is invalid.
+const EXIT_INVALID_CONFIG: u8 = 2;
+/// Exit code when a checked channel would be included in an update.
+const EXIT_FLAGGED: u8 = 3;
+
+/// Slack bot helper for managing outdated and very small channels.
+#[derive(Debug, Parser)]
+#[command(name = "archive-bot", version)]
+struct Cli {
+  /// Path to a TOML config file.
+  #[arg(short, long, env = "ARCHIVE_BOT_CONFIG", global = true)]
+  config: Option<PathBuf>,
+  /// Slack bot token, Mattermost access token, Discord bot token, or Enterprise Grid org admin token.
+  #[arg(long, env = "SLACK_BOT_TOKEN", hide_env_values = true, global = true)]
+  token: Option<String>,
+  /// ID of the channel to post updates in.
+  #[arg(long, env = "SLACK_CHANNEL_ID", global = true)]
+  channel_id: Option<String>,
+  /// Log more; repeat for more detail.
+  #[arg(short, long, action = ArgAction::Count, global = true)]
+  verbose: u8,
+  #[command(subcommand)]
+  command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+  /// Scan channels, then post updates and notify creators as configured.
+  Run {
+    /// Print what would be done instead of doing it.
+    #[arg(long)]
+    dry_run: bool,
+    /// File to write Prometheus metrics to once done, e.g. for node_exporter's textfile collector.
+    #[arg(long, env = "ARCHIVE_BOT_METRICS_FILE")]
+    metrics_file: Option<PathBuf>,
+  },
+  /// Post an update to the notification channel only. Skips the secondary channel, creator DMs and CSV upload.
+  Report {
+    /// Print the update instead of posting it.
+    #[arg(long)]
+    dry_run: bool,
+    /// File to write Prometheus metrics to once done, e.g. for node_exporter's textfile collector.
+    #[arg(long, env = "ARCHIVE_BOT_METRICS_FILE")]
+    metrics_file: Option<PathBuf>,
+  },
+  /// List channels and why they'd be reported. Doesn't join channels.
+  ListChannels,
+  /// Export the analysis of every channel. Doesn't join channels.
+  Export {
+    /// Format to export in: "json", "jsonl" or "csv".
+    #[arg(short, long, default_value = "csv")]
+    format: String,
+    /// File to write to; stdout if not set.
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+  },
+  /// Check a single channel, by ID or name. Exits with 3 if it would be reported.
+  Check {
+    /// Channel ID or name.
+    channel: String,
+  },
+  /// Archive a channel, by ID or name.
+  Archive {
+    /// Channel ID or name.
+    channel: String,
+  },
+  /// Check the configuration, and that the token works.
+  ValidateConfig,
+  /// Keep running, and run the bot on a schedule. Stops on SIGTERM or Ctrl+C.
+  Daemon {
+    /// Cron expression to run on, with seconds, e.g. "0 0 9 * * Mon".
+    #[arg(long, env = "ARCHIVE_BOT_CRON", conflicts_with = "interval")]
+    cron: Option<String>,
+    /// Run every so many seconds instead, starting immediately.
+    #[arg(long, env = "ARCHIVE_BOT_INTERVAL")]
+    interval: Option<u32>,
+    /// Timezone to evaluate the cron expression in, e.g. "Europe/Berlin".
+    #[arg(long, env = "ARCHIVE_BOT_TIMEZONE", default_value = "UTC")]
+    timezone: String,
+    /// Address to serve a health check on, at GET /health, and metrics at GET /metrics.
+    #[arg(long, env = "ARCHIVE_BOT_HEALTH_ADDR")]
+    health_addr: Option<SocketAddr>,
+  },
+}
+
+/// Config file contents. Anything not set uses Archive Bot's defaults.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Config {
+  /// "slack", "mattermost", "discord" or "enterprise_grid".
+  platform: Option<String>,
+  mattermost: Option<MattermostConfig>,
+  discord: Option<DiscordConfig>,
+  enterprise_grid: Option<EnterpriseGridConfig>,
+  token: Option<String>,
+  notification_channel_id: Option<String>,
+  filter_prefixes: Option<Vec<String>>,
+  /// "all", "never", or a list of channel prefixes.
+  join_channels: Option<JoinChannels>,
+  leave_joined_channels: Option<bool>,
+  count_humans_only: Option<bool>,
+  /// "include", "exclude", "separate" or "never_archive".
+  shared_channels: Option<String>,
+  upload_csv: Option<bool>,
+  /// File to write an HTML report to.
+  html_report: Option<String>,
+  /// JSON Lines file to append a record of every action to.
+  audit_log: Option<String>,
+  /// File to keep a history of runs in, for trends in the HTML report.
+  html_report_history: Option<String>,
+  /// "en", "de" or "ja".
+  locale: Option<String>,
+  /// Locale of messages posted to specific channels, by channel id.
+  channel_locales: Option<HashMap<String, String>>,
+  message_headers: Option<Vec<String>>,
+  #[serde(default)]
+  templates: TemplatesConfig,
+  #[serde(default)]
+  dates: DatesConfig,
+  stale_after: Option<u32>,
+  small_channel_threshold: Option<u16>,
+  min_channel_age: Option<u32>,
+  /// "single" or "threaded".
+  report_mode: Option<String>,
+  /// Any of "last_activity", "members" and "name", in order of precedence.
+  sort_by: Option<Vec<String>>,
+  notify_secondary_channel: Option<bool>,
+  secondary_notification_channel_id: Option<String>,
+  secondary_message_headers: Option<Vec<String>>,
+  notify_creators: Option<bool>,
+  creator_message_headers: Option<Vec<String>>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct MattermostConfig {
+  url: String,
+  team_id: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DiscordConfig {
+  guild_id: String,
+  /// Category to move archived channels into.
+  archive_category: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct EnterpriseGridConfig {
+  /// Workspaces to manage; every workspace in the organization if not set.
+  team_ids: Option<Vec<String>>,
+  /// Bot token to post with, rather than the org admin token.
+  bot_token: Option<String>,
+}
+
+/// Chat platform to manage channels on.
+#[derive(Debug)]
+enum Backend {
+  Slack,
+  Mattermost(Mattermost),
+  Discord(Discord),
+  EnterpriseGrid(EnterpriseGrid),
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum JoinChannels {
+  Policy(String),
+  Prefixed(Vec<String>),
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TemplatesConfig {
+  header: Option<String>,
+  line: Option<String>,
+  footer: Option<String>,
+  secondary: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DatesConfig {
+  token: Option<String>,
+  fallback_format: Option<String>,
+  /// IANA timezone name, e.g. "Europe/Berlin".
+  timezone: Option<String>,
+  relative: Option<bool>,
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+  let cli = Cli::parse();
+  let level = match cli.verbose {
+    0 => simplelog::LevelFilter::Warn,
+    1 => simplelog::LevelFilter::Info,
+    2 => simplelog::LevelFilter::Debug,
+    _ => simplelog::LevelFilter::Trace,
+  };
+  simplelog::TermLogger::init(level, simplelog::Config::default(), simplelog::TerminalMode::Stderr, simplelog::ColorChoice::Auto).ok();
+
+  let (bot, backend) = match load(&cli) {
+    Ok(loaded) => loaded,
+    Err(e) => {
+      eprintln!("Invalid configuration: {}", e);
+      return ExitCode::from(EXIT_INVALID_CONFIG);
+    },
+  };
+  match backend {
+    Backend::Slack => execute(bot, cli.command).await,
+    Backend::Mattermost(mattermost) => execute(bot.with_platform(mattermost), cli.command).await,
+    Backend::Discord(discord) => execute(bot.with_platform(discord), cli.command).await,
+    Backend::EnterpriseGrid(grid) => execute(bot.with_platform(grid), cli.command).await,
+  }
+}
+
+/// Run a command against the configured platform.
+async fn execute<P: ChatPlatform>(bot: ArchiveBot<P>, command: Command) -> ExitCode {
+  if !matches!(command, Command::ValidateConfig) {
+    if let Some(e) = bot.validate().first() {
+      eprintln!("Invalid configuration: {}", e);
+      return ExitCode::from(EXIT_INVALID_CONFIG);
+    }
+  }
+
+  match command {
+    Command::Run { dry_run, metrics_file } => run(ArchiveBot { dry_run, ..bot }, metrics_file).await,
+    Command::Report { dry_run, metrics_file } => run(ArchiveBot {
+      dry_run,
+      notify_secondary_channel: false,
+      notify_creators: false,
+      upload_csv: false,
+      ..bot
+    }, metrics_file).await,
+    Command::ListChannels => list_channels(ArchiveBot { dry_run: true, ..bot }).await,
+    Command::Export { format, output } => {
+      let format = match format.as_str() {
+        "json" => ExportFormat::Json,
+        "jsonl" => ExportFormat::JsonLines,
+        "csv" => ExportFormat::Csv,
+        _ => {
+          eprintln!("Unknown export format \"{}\"", format);
+          return ExitCode::from(EXIT_INVALID_CONFIG);
+        },
+      };
+      export(ArchiveBot { dry_run: true, ..bot }, format, output).await
+    },
+    Command::Check { channel } => check(ArchiveBot { dry_run: true, ..bot }, &channel).await,
+    Command::Archive { channel } => archive(bot, &channel).await,
+    Command::ValidateConfig => validate_config(bot).await,
+    Command::Daemon { cron, interval, timezone, health_addr } => {
+      let schedule = match (cron, interval) {
+        (_, Some(seconds)) => Schedule::Interval(seconds),
+        (Some(cron), None) => Schedule::Cron(leak(cron)),
+        (None, None) => Daemon::default().schedule,
+      };
+      let timezone = match timezone.parse() {
+        Ok(tz) => tz,
+        Err(e) => {
+          eprintln!("Invalid configuration: {}", e);
+          return ExitCode::from(EXIT_INVALID_CONFIG);
+        },
+      };
+      let daemon = Daemon { bot, schedule, timezone, health_addr };
+      if let Some(e) = daemon.validate().first() {
+        eprintln!("Invalid configuration: {}", e);
+        return ExitCode::from(EXIT_INVALID_CONFIG);
+      }
+      run_daemon(daemon).await
+    },
+  }
+}
+
+/// Build the bot's configuration from the config file, environment and flags.
+fn load(cli: &Cli) -> Result<(ArchiveBot, Backend), String> {
+  let config: Config = match &cli.config {
+    Some(path) => {
+      let contents = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+      toml::from_str(&contents).map_err(|e| format!("{}: {}", path.display(), e))?
+    },
+    None => Config::default(),
+  };
+
+  let platform = config.platform.as_deref().unwrap_or("slack");
+  if config.mattermost.is_some() && platform != "mattermost" {
+    return Err("mattermost is set, but platform is not \"mattermost\"".to_string());
+  }
+  if config.discord.is_some() && platform != "discord" {
+    return Err("discord is set, but platform is not \"discord\"".to_string());
+  }
+  if config.enterprise_grid.is_some() && platform != "enterprise_grid" {
+    return Err("enterprise_grid is set, but platform is not \"enterprise_grid\"".to_string());
+  }
+  let backend = match (platform, config.mattermost, config.discord) {
+    ("slack", _, _) => Backend::Slack,
+    ("mattermost", Some(mattermost), _) => Backend::Mattermost(Mattermost::new(&mattermost.url, &mattermost.team_id)),
+    ("discord", _, Some(discord)) => {
+      let mut backend = Discord::new(&discord.guild_id);
+      if let Some(category) = discord.archive_category {
+        backend.archive_category = category;
+      }
+      Backend::Discord(backend)
+    },
+    ("enterprise_grid", _, _) => {
+      let grid = config.enterprise_grid.unwrap_or_default();
+      let mut backend = EnterpriseGrid::new(grid.team_ids.map(leak_all).unwrap_or_default());
+      backend.bot_token = grid.bot_token;
+      Backend::EnterpriseGrid(backend)
+    },
+    ("mattermost" | "discord", _, _) => return Err(format!("platform is \"{0}\", but {0} is not set", platform)),
+    (platform, _, _) => return Err(format!("unknown platform \"{}\"", platform)),
+  };
+
+  let default = ArchiveBot::default();
+  let bot = ArchiveBot {
+    token: cli.token.clone().or(config.token).unwrap_or_default(),
+    notification_channel_id: cli.channel_id.clone().or(config.notification_channel_id).unwrap_or_default(),
+    filter_prefixes: config.filter_prefixes.map(leak_all).unwrap_or(default.filter_prefixes),
+    join_channels: match config.join_channels {
+      None => default.join_channels,
+      Some(JoinChannels::Prefixed(prefixes)) => JoinPolicy::Prefixed(leak_all(prefixes)),
+      Some(JoinChannels::Policy(policy)) => match policy.as_str() {
+        "all" => JoinPolicy::All,
+        "never" => JoinPolicy::Never,
+        _ => return Err(format!("unknown join_channels \"{}\"", policy)),
+      },
+    },
+    leave_joined_channels: config.leave_joined_channels.unwrap_or(default.leave_joined_channels),
+    count_humans_only: config.count_humans_only.unwrap_or(default.count_humans_only),
+    shared_channels: match config.shared_channels.as_deref() {
+      None => default.shared_channels,
+      Some("include") => SharedChannelPolicy::Include,
+      Some("exclude") => SharedChannelPolicy::Exclude,
+      Some("separate") => SharedChannelPolicy::Separate,
+      Some("never_archive") => SharedChannelPolicy::NeverArchive,
+      Some(policy) => return Err(format!("unknown shared_channels \"{}\"", policy)),
+    },
+    upload_csv: config.upload_csv.unwrap_or(default.upload_csv),
+    audit_log: match config.audit_log {
+      Some(path) => Some(Box::new(AuditFile { path: leak(path) })),
+      None => default.audit_log,
+    },
+    html_report: match (config.html_report, config.html_report_history) {
+      (Some(path), history) => Some(HtmlReport { path: leak(path), history_path: history.map(leak) }),
+      (None, Some(_)) => return Err("html_report_history is set, but html_report is not".to_string()),
+      (None, None) => default.html_report,
+    },
+    locale: match config.locale.as_deref() {
+      None => default.locale,
+      Some(locale) => parse_locale(locale)?,
+    },
+    channel_locales: match config.channel_locales {
+      None => default.channel_locales,
+      Some(locales) => locales.into_iter()
+        .map(|(channel_id, locale)| Ok((leak(channel_id), parse_locale(&locale)?)))
+        .collect::<Result<_, String>>()?,
+    },
+    message_headers: config.message_headers.map(leak_all).unwrap_or(default.message_headers),
+    templates: Templates {
+      header: config.templates.header.map(leak),
+      line: config.templates.line.map(leak),
+      footer: config.templates.footer.map(leak),
+      secondary: config.templates.secondary.map(leak),
+    },
+    dates: DateFormat {
+      token: config.dates.token.map(leak).unwrap_or(default.dates.token),
+      fallback_format: config.dates.fallback_format.map(leak).or(default.dates.fallback_format),
+      timezone: match config.dates.timezone {
+        None => default.dates.timezone,
+        Some(timezone) => timezone.parse().map_err(|e| format!("dates.timezone: {}", e))?,
+      },
+      relative: config.dates.relative.unwrap_or(default.dates.relative),
+    },
+    stale_after: config.stale_after.unwrap_or(default.stale_after),
+    small_channel_threshold: config.small_channel_threshold.unwrap_or(default.small_channel_threshold),
+    min_channel_age: config.min_channel_age.unwrap_or(default.min_channel_age),
+    report_mode: match config.report_mode.as_deref() {
+      None => default.report_mode,
+      Some("single") => ReportMode::Single,
+      Some("threaded") => ReportMode::Threaded,
+      Some(mode) => return Err(format!("unknown report_mode \"{}\"", mode)),
+    },
+    sort_by: match config.sort_by {
+      None => default.sort_by,
+      Some(keys) => keys.iter().map(|key| match key.as_str() {
+        "last_activity" => Ok(SortKey::LastActivity),
+        "members" => Ok(SortKey::Members),
+        "name" => Ok(SortKey::Name),
+        _ => Err(format!("unknown sort_by \"{}\"", key)),
+      }).collect::<Result<_, String>>()?,
+    },
+    notify_secondary_channel: config.notify_secondary_channel.unwrap_or(default.notify_secondary_channel),
+    secondary_notification_channel_id: config.secondary_notification_channel_id.unwrap_or(default.secondary_notification_channel_id),
+    secondary_message_headers: config.secondary_message_headers.map(leak_all).unwrap_or(default.secondary_message_headers),
+    notify_creators: config.notify_creators.unwrap_or(default.notify_creators),
+    creator_message_headers: config.creator_message_headers.map(leak_all).unwrap_or(default.creator_message_headers),
+    ..default
+  };
+  Ok((bot, backend))
+}
+
+/// Parse a locale code, e.g. "de".
+fn parse_locale(locale: &str) -> Result<Locale, String> {
+  match locale {
+    "en" => Ok(Locale::En),
+    "de" => Ok(Locale::De),
+    "ja" => Ok(Locale::Ja),
+    _ => Err(format!("unknown locale \"{}\"", locale)),
+  }
+}
+
+/// Configuration lives for the whole run, so it's fine to leak it into the `'static` strings the bot expects.
+fn leak(s: String) -> &'static str {
+  Box::leak(s.into_boxed_str())
+}
+
+fn leak_all(v: Vec<String>) -> Vec<&'static str> {
+  v.into_iter().map(leak).collect()
+}
+
+async fn run<P: ChatPlatform>(bot: ArchiveBot<P>, metrics_file: Option<PathBuf>) -> ExitCode {
+  let result = bot.run().await;
+  if let Some(path) = metrics_file {
+    if let Err(e) = Metrics::global().write_textfile(&path) {
+      eprintln!("Unable to write metrics to {}: {}", path.display(), e);
+    }
+  }
+  match result {
+    Ok(report) => {
+      for action in &report.actions {
+        println!("{}", describe(action));
+      }
+      for action in &report.failed {
+        eprintln!("Failed: {}", describe(action));
+      }
+      match report.failed.is_empty() {
+        true => ExitCode::SUCCESS,
+        false => ExitCode::from(EXIT_FAILURE),
+      }
+    },
+    Err(e) => {
+      eprintln!("{}", e);
+      ExitCode::from(EXIT_FAILURE)
+    },
+  }
+}
+
+async fn list_channels<P: ChatPlatform>(bot: ArchiveBot<P>) -> ExitCode {
+  let mut channels = bot.scan().await;
+  if channels.is_empty() {
+    eprintln!("No channels found");
+    return ExitCode::from(EXIT_FAILURE);
+  }
+  channels.sort_by(|a, b| a.name.cmp(&b.name));
+  for channel in channels {
+    println!("{}\t#{}\t{}\t{}\t{}", channel.id, channel.name, channel.num_members, format_date(channel.last_activity()), flags(&channel));
+  }
+  ExitCode::SUCCESS
+}
+
+async fn export<P: ChatPlatform>(bot: ArchiveBot<P>, format: ExportFormat, output: Option<PathBuf>) -> ExitCode {
+  let channels = bot.scan().await;
+  let result = match &output {
+    Some(path) => File::create(path).and_then(|file| format.write(&channels, BufWriter::new(file))),
+    None => format.write(&channels, io::stdout().lock()),
+  };
+  match result {
+    Ok(_) => ExitCode::SUCCESS,
+    Err(e) => {
+      eprintln!("Unable to export channels: {}", e);
+      ExitCode::from(EXIT_FAILURE)
+    },
+  }
+}
+
+async fn check<P: ChatPlatform>(bot: ArchiveBot<P>, channel: &str) -> ExitCode {
+  let channel = match bot.check_channel(channel).await {
+    Some(channel) => channel,
+    None => {
+      eprintln!("Channel {} not found", channel);
+      return ExitCode::from(EXIT_FAILURE);
+    },
+  };
+  println!("#{} ({})", channel.name, channel.id);
+  println!("Members: {}", channel.num_members);
+  println!("Last activity: {}", format_date(channel.last_activity()));
+  println!("Flags: {}", flags(&channel));
+  match bot.channel_is_flagged(&channel) {
+    true => ExitCode::from(EXIT_FLAGGED),
+    false => ExitCode::SUCCESS,
+  }
+}
+
+async fn archive<P: ChatPlatform>(bot: ArchiveBot<P>, channel: &str) -> ExitCode {
+  let name = channel.trim_start_matches('#');
+  let channels = bot.get_channels().await;
+  let Some(channel) = channels.iter().find(|c| c.id == channel || c.name == name) else {
+    eprintln!("Channel {} not found", channel);
+    return ExitCode::from(EXIT_FAILURE);
+  };
+  match bot.archive_channel(channel).await {
+    Ok(_) => {
+      println!("Archived {}", channel.id);
+      ExitCode::SUCCESS
+    },
+    Err(ArchiveError::Shared) => {
+      eprintln!("Not archiving {}: it's shared with another organization", channel.id);
+      ExitCode::from(EXIT_FAILURE)
+    },
+    Err(e) => {
+      eprintln!("Unable to archive {}: {}", channel.id, e);
+      ExitCode::from(EXIT_FAILURE)
+    },
+  }
+}
+
+async fn validate_config<P: ChatPlatform>(bot: ArchiveBot<P>) -> ExitCode {
+  let errors = bot.validate();
+  if !errors.is_empty() {
+    for e in errors {
+      eprintln!("Invalid configuration: {}", e);
+    }
+    return ExitCode::from(EXIT_INVALID_CONFIG);
+  }
+  match bot.get_bot_name().await {
+    Some(name) => {
+      println!("Configuration is valid; authenticated as @{}", name);
+      ExitCode::SUCCESS
+    },
+    None => {
+      eprintln!("Unable to authenticate with the configured token");
+      ExitCode::from(EXIT_FAILURE)
+    },
+  }
+}
+
+async fn run_daemon<P: ChatPlatform>(daemon: Daemon<P>) -> ExitCode {
+  match daemon.run().await {
+    Ok(_) => ExitCode::SUCCESS,
+    Err(e) => {
+      eprintln!("{}", e);
+      ExitCode::from(EXIT_FAILURE)
+    },
+  }
+}
+
+/// Describe an action, including any message text.
+fn describe(action: &Action) -> String {
+  match action {
+    Action::Join { channel_id } => format!("Join {}", channel_id),
+    Action::Leave { channel_id } => format!("Leave {}", channel_id),
+    Action::Post { channel_id, text, replies } => {
+      let mut description = format!("Post in {}:\n{}", channel_id, text);
+      for reply in replies {
+        description.push_str(&format!("\n  Reply: {}", reply));
+      }
+      description
+    },
+    Action::DirectMessage { user_id, text } => format!("Message {}:\n{}", user_id, text),
+    Action::Upload { channel_id, filename, .. } => format!("Upload {} to {}", filename, channel_id),
+  }
+}
+
+/// Format a timestamp as a date, or "never" if there's no activity.
+fn format_date(ts: i64) -> String {
+  match chrono::NaiveDateTime::from_timestamp_opt(ts, 0) {
+    Some(date) if ts > 0 => date.format("%Y-%m-%d").to_string(),
+    _ => "never".to_string(),
+  }
+}
+
+/// List what's notable about a channel, e.g. why it would be reported.
+fn flags(channel: &ChannelData) -> String {
+  let flags: Vec<&str> = [
+    (channel.is_ignored, "ignored"),
+    (channel.is_new, "new"),
+    (channel.is_old, "stale"),
+    (channel.is_small, "small"),
+    (channel.is_private, "private"),
+    (channel.is_ext_shared, "shared"),
+    (channel.is_pending_ext_shared, "pending-shared"),
+    (channel.is_unreadable(), "unreadable"),
+    (channel.is_history_unknown(), "history-unknown"),
+  ].iter().filter(|(set, _)| *set).map(|(_, flag)| *flag).collect();
+  match flags.is_empty() {
+    true => "-".to_string(),
+    false => flags.join(","),
+  }
+}
@@ -1,28 +1,44 @@
-use crate::ArchiveBot;
+use std::time::Instant;
+use tracing::{field, Instrument, Span};
+
+use crate::slack::Slack;
 use crate::types::*;
 use crate::error::SlackError;
 
-impl ArchiveBot {
+impl Slack {
   /// Wrapper for reqwest client to make Slack API calls.
-  pub async fn slack_query<'sq>(&self, method: &str, params: &mut UrlParams<'sq>) -> Result<String, reqwest::Error> {
-    let mut p: UrlParams = vec![("token", self.token.to_owned())];
+  pub async fn slack_query<'sq>(&self, token: &str, method: &str, params: &mut UrlParams<'sq>) -> Result<String, reqwest::Error> {
+    let mut p: UrlParams = vec![("token", token.to_owned())];
     p.append(params);
 
     let url = format!("https://slack.com/api/{}", method);
     let client = reqwest::Client::new();
-    Ok(client.post(url).form(&p).send().await?.text().await?)
+    let response = client.post(url).form(&p).send().await?;
+    Span::current().record("status", response.status().as_u16());
+    response.text().await
   }
 
   /// Send specific API call and parse response.
-  pub async fn send<'sq>(&self, method: &str, params: &mut UrlParams<'sq>) -> Result<SlackResponse, SlackError<reqwest::Error>> {
-    let response = self.slack_query(method, params).await;
+  /// Each call is traced in a `slack_api` span, with its method, HTTP status, error, retries and duration.
+  /// Slack calls are never retried, so `retries` is always 0.
+  pub async fn send<'sq>(&self, token: &str, method: &str, params: &mut UrlParams<'sq>) -> Result<SlackResponse, SlackError<reqwest::Error>> {
+    let span = tracing::info_span!("slack_api", method, status = field::Empty, error = field::Empty, retries = 0, duration_ms = field::Empty);
+    let start = Instant::now();
+    let response = self.slack_query(token, method, params).instrument(span.clone()).await;
 
-    response.map_err(SlackError::Client)
+    let result = response.map_err(SlackError::Client)
       .and_then(|result| {
         serde_json::from_str::<SlackResponse>(&result)
           .map_err(|e| SlackError::MalformedResponse(result, e))
       })
-      .and_then(|o| o.into())
+      .and_then(|o| o.into());
+    span.record("duration_ms", start.elapsed().as_millis() as u64);
+    if let Err(e) = &result {
+      span.record("error", field::display(e));
+    }
+    #[cfg(feature = "metrics")]
+    crate::Metrics::global().record_api_call(method, result.as_ref().err().map(SlackError::variant));
+    result
   }
 }
 
@@ -33,9 +49,9 @@ mod tests {
   async fn test_auth() {
     let bot = crate::ArchiveBot::_from_env_debug();
     let mut params: crate::types::UrlParams = vec![];
-    if let Ok(auth) = bot.send("auth.test", &mut params).await {
-      if let Some(user) = auth.user {
-        assert!(user != "");
+    if let Ok(auth) = bot.platform.send(&bot.token, "auth.test", &mut params).await {
+      if let Some(user) = auth.user.as_ref().and_then(crate::types::StringOr::as_str) {
+        assert!(!user.is_empty());
       }
     }
   }
@@ -0,0 +1,282 @@
+use std::error::Error;
+use std::net::SocketAddr;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use tracing::{debug, info, warn};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::watch;
+use tokio::time::timeout;
+
+use crate::ArchiveBot;
+use crate::platform::ChatPlatform;
+use crate::slack::Slack;
+
+/// How long to wait for a health check client to send its request.
+const HEALTH_READ_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// When to run the bot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Schedule {
+  /// A cron expression, with seconds, e.g. `0 0 9 * * Mon` for every Monday at 9:00.
+  /// Evaluated in the daemon's timezone.
+  Cron(&'static str),
+  /// Every so many seconds, starting immediately.
+  Interval(u32),
+}
+
+/// Run Archive Bot on a schedule until stopped by SIGTERM or Ctrl+C.
+#[derive(Debug)]
+pub struct Daemon<P = Slack> {
+  /// Bot configuration to run with.
+  pub bot: ArchiveBot<P>,
+  /// When to run the bot.
+  pub schedule: Schedule,
+  /// Timezone to evaluate cron expressions in.
+  pub timezone: Tz,
+  /// Address to serve a health check on, at `GET /health`, and with the `metrics` feature, metrics at
+  /// `GET /metrics`. Not served if `None`.
+  pub health_addr: Option<SocketAddr>,
+}
+
+impl Default for Daemon {
+  fn default() -> Daemon {
+    Daemon {
+      bot: ArchiveBot::default(),
+      schedule: Schedule::Cron("0 0 9 * * Mon"),
+      timezone: Tz::UTC,
+      health_addr: None,
+    }
+  }
+}
+
+/// State of the daemon, as reported by the health check.
+#[derive(Debug, Default)]
+struct Status {
+  running: bool,
+  last_run: Option<DateTime<Utc>>,
+  last_run_ok: bool,
+  next_run: Option<DateTime<Utc>>,
+}
+
+impl<P: ChatPlatform> Daemon<P> {
+  /// Check the configuration, including the bot's, for problems. Doesn't make any API calls.
+  pub fn validate(&self) -> Vec<String> {
+    let mut errors = self.bot.validate();
+    match self.schedule {
+      Schedule::Cron(expression) => if let Err(e) = cron::Schedule::from_str(expression) {
+        errors.push(format!("invalid cron expression \"{}\": {}", expression, e));
+      },
+      Schedule::Interval(0) => errors.push("interval must be at least one second".to_string()),
+      Schedule::Interval(_) => {},
+    }
+    errors
+  }
+
+  /// Run the bot on schedule until stopped.
+  /// A run in progress is finished before stopping, and runs never overlap; if a run takes longer
+  /// than the time until the next one, the missed runs are skipped.
+  pub async fn run(&self) -> Result<(), Box<dyn Error>> {
+    let cron = match self.schedule {
+      Schedule::Cron(expression) => Some(cron::Schedule::from_str(expression)?),
+      Schedule::Interval(_) => None,
+    };
+    let status = Arc::new(Mutex::new(Status::default()));
+
+    let health = match self.health_addr {
+      Some(addr) => {
+        let listener = TcpListener::bind(addr).await?;
+        info!(addr = %listener.local_addr()?, "Serving health check");
+        Some(tokio::spawn(serve_health(listener, status.clone())))
+      },
+      None => None,
+    };
+
+    let (stop, mut stopped) = watch::channel(false);
+    tokio::spawn(async move {
+      shutdown_signal().await;
+      info!("Stopping after any run in progress");
+      stop.send(true).ok();
+    });
+
+    let mut first = true;
+    loop {
+      let now = Utc::now();
+      let next = match self.schedule {
+        Schedule::Interval(_) if first => Some(now),
+        Schedule::Interval(seconds) => Some(now + chrono::Duration::seconds(seconds as i64)),
+        Schedule::Cron(_) => cron.as_ref()
+          .and_then(|cron| cron.after(&now.with_timezone(&self.timezone)).next())
+          .map(|next| next.with_timezone(&Utc)),
+      };
+      let next = match next {
+        Some(next) => next,
+        None => {
+          warn!("Schedule has no future runs");
+          break;
+        },
+      };
+      first = false;
+      status.lock().unwrap().next_run = Some(next);
+      debug!(next = %next, "Scheduled next run");
+
+      tokio::select! {
+        _ = tokio::time::sleep((next - now).to_std().unwrap_or_default()) => {},
+        _ = stopped.changed() => break,
+      }
+
+      // Not raced against shutdown, so posts in flight are finished.
+      status.lock().unwrap().running = true;
+      let result = self.bot.run().await;
+      if let Err(e) = &result {
+        warn!(error = %e, "Run failed");
+      }
+      let mut s = status.lock().unwrap();
+      s.running = false;
+      s.last_run = Some(Utc::now());
+      s.last_run_ok = result.is_ok();
+      drop(s);
+
+      if *stopped.borrow() {
+        break;
+      }
+    }
+
+    if let Some(health) = health {
+      health.abort();
+    }
+    info!("Stopped");
+    Ok(())
+  }
+}
+
+/// Wait for SIGTERM or Ctrl+C.
+async fn shutdown_signal() {
+  #[cfg(unix)]
+  {
+    use tokio::signal::unix::{signal, SignalKind};
+    match signal(SignalKind::terminate()) {
+      Ok(mut sigterm) => {
+        tokio::select! {
+          _ = sigterm.recv() => {},
+          _ = tokio::signal::ctrl_c() => {},
+        }
+      },
+      Err(e) => {
+        warn!(error = %e, "Unable to listen for SIGTERM");
+        tokio::signal::ctrl_c().await.ok();
+      },
+    }
+  }
+  #[cfg(not(unix))]
+  tokio::signal::ctrl_c().await.ok();
+}
+
+/// Serve a minimal HTTP health check: 200 unless the last run failed, with the status as JSON.
+/// Each connection is handled in its own task, so a slow client doesn't hold up the rest.
+async fn serve_health(listener: TcpListener, status: Arc<Mutex<Status>>) {
+  loop {
+    let stream = match listener.accept().await {
+      Ok((stream, _)) => stream,
+      Err(e) => {
+        warn!(error = %e, "Health check connection failed");
+        continue;
+      },
+    };
+    tokio::spawn(respond_health(stream, status.clone()));
+  }
+}
+
+/// Answer a single health check or metrics request.
+async fn respond_health(mut stream: TcpStream, status: Arc<Mutex<Status>>) {
+  let mut request = [0; 1024];
+  let len = match timeout(HEALTH_READ_TIMEOUT, stream.read(&mut request)).await {
+    Ok(Ok(len)) => len,
+    Ok(Err(e)) => {
+      debug!(error = %e, "Unable to read health check request");
+      return;
+    },
+    Err(_) => {
+      debug!("Timed out reading health check request");
+      return;
+    },
+  };
+  let request = &request[..len];
+  let response = match request {
+    _ if request.starts_with(b"GET /health ") => health_response(&status.lock().unwrap()),
+    #[cfg(feature = "metrics")]
+    _ if request.starts_with(b"GET /metrics ") => metrics_response(),
+    _ => "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string(),
+  };
+  if let Err(e) = stream.write_all(response.as_bytes()).await {
+    debug!(error = %e, "Unable to send health check response");
+  }
+}
+
+/// HTTP response for the health check.
+fn health_response(status: &Status) -> String {
+  let healthy = status.last_run.is_none() || status.last_run_ok;
+  let body = serde_json::json!({
+    "status": if healthy { "ok" } else { "failing" },
+    "running": status.running,
+    "last_run": status.last_run.map(|t| t.to_rfc3339()),
+    "last_run_ok": status.last_run.map(|_| status.last_run_ok),
+    "next_run": status.next_run.map(|t| t.to_rfc3339()),
+  }).to_string();
+  format!(
+    "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+    if healthy { "200 OK" } else { "503 Service Unavailable" },
+    body.len(),
+    body,
+  )
+}
+
+/// HTTP response for metrics, in Prometheus' text format.
+#[cfg(feature = "metrics")]
+fn metrics_response() -> String {
+  let body = crate::Metrics::global().render();
+  format!(
+    "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+    body.len(),
+    body,
+  )
+}
+
+#[cfg(test)]
+mod tests {
+  #[cfg(feature = "unit")]
+  use super::*;
+
+  /// Test health check responses.
+  #[test]
+  #[cfg(feature = "unit")]
+  fn test_health_response() {
+    let mut status = Status::default();
+    assert!(health_response(&status).starts_with("HTTP/1.1 200 OK"));
+
+    status.last_run = Some(Utc::now());
+    status.last_run_ok = false;
+    let response = health_response(&status);
+    assert!(response.starts_with("HTTP/1.1 503"));
+    assert!(response.contains("\"status\":\"failing\""));
+  }
+
+  /// Test that a client that never sends a request doesn't block the health check.
+  #[tokio::test]
+  #[cfg(feature = "unit")]
+  async fn test_health_idle_client() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(serve_health(listener, Arc::new(Mutex::new(Status::default()))));
+
+    let _idle = TcpStream::connect(addr).await.unwrap();
+    let mut client = TcpStream::connect(addr).await.unwrap();
+    client.write_all(b"GET /health HTTP/1.1\r\n\r\n").await.unwrap();
+    let mut response = String::new();
+    timeout(Duration::from_secs(1), client.read_to_string(&mut response)).await.unwrap().unwrap();
+    assert!(response.starts_with("HTTP/1.1 200 OK"));
+  }
+}
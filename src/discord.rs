@@ -0,0 +1,650 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::{debug, field, info_span, Instrument, Span};
+use reqwest::{Method, RequestBuilder};
+use reqwest::multipart::{Form, Part};
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use serde_json::json;
+
+use crate::error::DiscordError;
+use crate::platform::{translate_markup, ChatPlatform};
+use crate::types::{Channel, Message, Timestamp, User};
+
+/// Discord's API.
+const API_URL: &str = "https://discord.com/api/v10";
+/// First second of 2015, the start of Discord's ids, in milliseconds.
+const DISCORD_EPOCH: i64 = 1420070400000;
+/// Channel types.
+const GUILD_TEXT: u8 = 0;
+const GUILD_CATEGORY: u8 = 4;
+const GUILD_ANNOUNCEMENT: u8 = 5;
+/// Permission bits.
+const ADMINISTRATOR: u64 = 1 << 3;
+const VIEW_CHANNEL: u64 = 1 << 10;
+/// Permission overwrite types.
+const OVERWRITE_ROLE: u8 = 0;
+const OVERWRITE_MEMBER: u8 = 1;
+/// Largest page of guild members Discord returns.
+const MEMBERS_PER_PAGE: usize = 1000;
+/// Times to retry a rate-limited request.
+const MAX_RETRIES: u32 = 5;
+
+/// Discord's API, v10. Manages the text channels of a single server ("guild").
+///
+/// The token is a bot token. There's no joining channels on Discord: the bot reads every channel its
+/// roles let it view, and channel members are everyone whose roles let them view the channel, which
+/// needs the Server Members intent. Channels are archived by moving them into a category.
+#[derive(Debug)]
+pub struct Discord {
+  /// API URL.
+  pub url: String,
+  /// Id of the server whose channels to manage.
+  pub guild_id: String,
+  /// Name of the category to move archived channels into. Created if it doesn't exist.
+  pub archive_category: String,
+  /// Ids of the members who can view each channel, from the last time channels were listed.
+  viewers: Mutex<HashMap<String, Vec<String>>>,
+}
+
+/// Channel response. Non-comprehensive.
+#[derive(Debug, Deserialize)]
+struct DiscordChannel {
+  id: String,
+  #[serde(rename = "type")]
+  channel_type: u8,
+  #[serde(default)]
+  name: String,
+  parent_id: Option<String>,
+  #[serde(default)]
+  permission_overwrites: Vec<Overwrite>,
+}
+
+/// Permission overwrite for a role or member in a channel.
+#[derive(Debug, Deserialize)]
+struct Overwrite {
+  id: String,
+  #[serde(rename = "type")]
+  overwrite_type: u8,
+  allow: String,
+  deny: String,
+}
+
+/// Guild response. Non-comprehensive.
+#[derive(Debug, Deserialize)]
+struct Guild {
+  owner_id: String,
+  roles: Vec<Role>,
+}
+
+/// Role response. Non-comprehensive.
+#[derive(Debug, Deserialize)]
+struct Role {
+  id: String,
+  permissions: String,
+}
+
+/// Guild member response. Non-comprehensive.
+#[derive(Debug, Deserialize)]
+struct GuildMember {
+  user: DiscordUser,
+  #[serde(default)]
+  roles: Vec<String>,
+}
+
+/// User response. Non-comprehensive.
+#[derive(Debug, Deserialize)]
+struct DiscordUser {
+  id: String,
+  username: String,
+  #[serde(default)]
+  bot: bool,
+}
+
+/// Message response. Non-comprehensive.
+#[derive(Debug, Deserialize)]
+struct DiscordMessage {
+  id: String,
+  channel_id: String,
+  #[serde(rename = "type", default)]
+  message_type: u8,
+  author: DiscordUser,
+  #[serde(default)]
+  content: String,
+}
+
+/// Error response.
+#[derive(Debug, Default, Deserialize)]
+struct ErrorResponse {
+  #[serde(default)]
+  code: u64,
+  #[serde(default)]
+  message: String,
+}
+
+/// Rate limit response.
+#[derive(Debug, Deserialize)]
+struct RateLimit {
+  retry_after: f64,
+}
+
+/// Everything needed to work out who can view a channel.
+#[derive(Debug)]
+struct Access {
+  guild_id: String,
+  owner_id: String,
+  /// Permissions by role id. The `@everyone` role's id is the guild's id.
+  roles: HashMap<String, u64>,
+  members: Vec<GuildMember>,
+}
+
+impl Access {
+  /// A member's permissions in a channel, or `@everyone`'s if `member` is `None`.
+  /// https://discord.com/developers/docs/topics/permissions#permission-overwrites
+  fn permissions(&self, member: Option<&GuildMember>, overwrites: &[Overwrite]) -> u64 {
+    let roles: &[String] = member.map(|m| m.roles.as_slice()).unwrap_or_default();
+    if member.is_some_and(|m| m.user.id == self.owner_id) {
+      return u64::MAX;
+    }
+    let mut permissions = roles.iter().chain([&self.guild_id])
+      .filter_map(|id| self.roles.get(id))
+      .fold(0, |p, role| p | role);
+    if permissions & ADMINISTRATOR != 0 {
+      return u64::MAX;
+    }
+
+    let apply = |permissions: u64, allow: u64, deny: u64| (permissions & !deny) | allow;
+    if let Some(everyone) = overwrites.iter().find(|o| o.id == self.guild_id) {
+      permissions = apply(permissions, parse_permissions(&everyone.allow), parse_permissions(&everyone.deny));
+    }
+    let (allow, deny) = overwrites.iter()
+      .filter(|o| o.overwrite_type == OVERWRITE_ROLE && roles.contains(&o.id))
+      .fold((0, 0), |(allow, deny), o| (allow | parse_permissions(&o.allow), deny | parse_permissions(&o.deny)));
+    permissions = apply(permissions, allow, deny);
+    if let Some(o) = member.and_then(|m| overwrites.iter().find(|o| o.overwrite_type == OVERWRITE_MEMBER && o.id == m.user.id)) {
+      permissions = apply(permissions, parse_permissions(&o.allow), parse_permissions(&o.deny));
+    }
+    permissions
+  }
+
+  /// Ids of the members who can view a channel.
+  fn viewers(&self, overwrites: &[Overwrite]) -> Vec<String> {
+    self.members.iter()
+      .filter(|m| self.permissions(Some(m), overwrites) & VIEW_CHANNEL != 0)
+      .map(|m| m.user.id.clone())
+      .collect()
+  }
+
+  /// Whether `@everyone` can't view a channel.
+  fn is_private(&self, overwrites: &[Overwrite]) -> bool {
+    self.permissions(None, overwrites) & VIEW_CHANNEL == 0
+  }
+}
+
+impl Discord {
+  /// Manage a server's channels.
+  pub fn new(guild_id: &str) -> Discord {
+    Discord {
+      url: API_URL.to_string(),
+      guild_id: guild_id.to_string(),
+      archive_category: "Archive".to_string(),
+      viewers: Mutex::new(HashMap::new()),
+    }
+  }
+
+  /// Start an authenticated API request.
+  fn request(&self, token: &str, method: Method, path: &str) -> RequestBuilder {
+    reqwest::Client::new()
+      .request(method, format!("{}{}", self.url, path))
+      .header("Authorization", format!("Bot {}", token))
+  }
+
+  /// Send a request and parse the response, waiting and retrying if rate limited.
+  /// Each request is traced in a `discord_api` span, with its path, HTTP status, retries and duration.
+  async fn send<T: DeserializeOwned>(&self, request: impl Fn() -> RequestBuilder) -> Result<T, DiscordError> {
+    let (method, path) = request().build()
+      .map(|r| (r.method().to_string(), r.url().path().to_string()))
+      .unwrap_or_default();
+    let span = info_span!("discord_api", method, path, status = field::Empty, retries = field::Empty, duration_ms = field::Empty);
+    let start = Instant::now();
+    let mut retries = 0;
+    let result = async {
+      loop {
+        let response = request().send().await.map_err(DiscordError::Client)?;
+        let status = response.status();
+        Span::current().record("status", status.as_u16());
+        let body = response.text().await.map_err(DiscordError::Client)?;
+        if status.as_u16() == 429 && retries < MAX_RETRIES {
+          if let Ok(limit) = serde_json::from_str::<RateLimit>(&body) {
+            debug!(retry_after = limit.retry_after, "Rate limited; retrying");
+            #[cfg(feature = "metrics")]
+            crate::Metrics::global().record_rate_limit_wait(Duration::from_secs_f64(limit.retry_after));
+            tokio::time::sleep(Duration::from_secs_f64(limit.retry_after)).await;
+            retries += 1;
+            continue;
+          }
+        }
+        if !status.is_success() {
+          let error: ErrorResponse = serde_json::from_str(&body).unwrap_or_default();
+          return Err(DiscordError::Api { status: status.as_u16(), code: error.code, message: error.message });
+        }
+        return serde_json::from_str(&body).map_err(|e| DiscordError::MalformedResponse(body, e));
+      }
+    }.instrument(span.clone()).await;
+    span.record("retries", retries);
+    span.record("duration_ms", start.elapsed().as_millis() as u64);
+    result
+  }
+
+
+  /// Every channel in the server, including categories.
+  async fn guild_channels(&self, token: &str) -> Result<Vec<DiscordChannel>, DiscordError> {
+    let path = format!("/guilds/{}/channels", self.guild_id);
+    self.send(|| self.request(token, Method::GET, &path)).await
+  }
+
+  /// Every member of the server.
+  async fn guild_members(&self, token: &str) -> Result<Vec<GuildMember>, DiscordError> {
+    let path = format!("/guilds/{}/members", self.guild_id);
+    let mut all: Vec<GuildMember> = vec![];
+    loop {
+      let after = all.last().map(|m| m.user.id.clone()).unwrap_or_default();
+      let page: Vec<GuildMember> = self.send(|| {
+        self.request(token, Method::GET, &path).query(&[("limit", MEMBERS_PER_PAGE.to_string()), ("after", after.clone())])
+      }).await?;
+      let last = page.len() < MEMBERS_PER_PAGE;
+      all.extend(page);
+      if last {
+        break;
+      }
+    }
+    Ok(all)
+  }
+
+  /// Roles and members of the server.
+  async fn access(&self, token: &str) -> Result<Access, DiscordError> {
+    let path = format!("/guilds/{}", self.guild_id);
+    let guild: Guild = self.send(|| self.request(token, Method::GET, &path)).await?;
+    Ok(Access {
+      guild_id: self.guild_id.clone(),
+      owner_id: guild.owner_id,
+      roles: guild.roles.into_iter().map(|r| (r.id, parse_permissions(&r.permissions))).collect(),
+      members: self.guild_members(token).await?,
+    })
+  }
+
+  /// The bot's own user.
+  async fn me(&self, token: &str) -> Result<DiscordUser, DiscordError> {
+    self.send(|| self.request(token, Method::GET, "/users/@me")).await
+  }
+}
+
+impl ChatPlatform for Discord {
+  type Error = DiscordError;
+
+  const MAX_MESSAGE_LENGTH: usize = 2000;
+
+  // The bot reads every channel its roles let it view; there's nothing to join.
+  const READS_WITHOUT_JOINING: bool = true;
+
+  // Access to channels is set with roles and permission overwrites, not invites.
+  const HAS_INVITE_COMMAND: bool = false;
+
+  async fn channels(&self, token: &str) -> Result<Vec<Channel>, DiscordError> {
+    let bot_id = self.me(token).await?.id;
+    let access = self.access(token).await?;
+    let channels = self.guild_channels(token).await?;
+    let archive_id = channels.iter()
+      .find(|c| c.channel_type == GUILD_CATEGORY && c.name == self.archive_category)
+      .map(|c| c.id.clone());
+
+    let mut viewers = self.viewers.lock().unwrap();
+    viewers.clear();
+    Ok(channels.into_iter()
+      .filter(|c| c.channel_type == GUILD_TEXT || c.channel_type == GUILD_ANNOUNCEMENT)
+      .filter(|c| archive_id.is_none() || c.parent_id != archive_id)
+      .map(|c| {
+        let members = access.viewers(&c.permission_overwrites);
+        let is_private = access.is_private(&c.permission_overwrites);
+        let channel = Channel {
+          is_channel: !is_private,
+          is_private,
+          is_member: members.contains(&bot_id),
+          created: Some(snowflake_timestamp(&c.id)),
+          num_members: members.len() as i32,
+          id: c.id.clone(),
+          name: c.name,
+          ..Channel::default()
+        };
+        viewers.insert(c.id, members);
+        channel
+      })
+      .collect())
+  }
+
+  async fn history(&self, token: &str, channel_id: &str, limit: u16) -> Result<Option<Vec<Message>>, DiscordError> {
+    let path = format!("/channels/{}/messages", channel_id);
+    let messages: Vec<DiscordMessage> = match self.send(|| self.request(token, Method::GET, &path).query(&[("limit", limit.min(100))])).await {
+      Ok(messages) => messages,
+      Err(DiscordError::Api { status: 403, .. }) => return Ok(None),
+      Err(e) => return Err(e),
+    };
+    Ok(Some(messages.into_iter().map(Message::from).collect()))
+  }
+
+  async fn members(&self, token: &str, channel_id: &str) -> Result<Vec<String>, DiscordError> {
+    if let Some(members) = self.viewers.lock().unwrap().get(channel_id) {
+      return Ok(members.clone());
+    }
+    let path = format!("/channels/{}", channel_id);
+    let channel: DiscordChannel = self.send(|| self.request(token, Method::GET, &path)).await?;
+    Ok(self.access(token).await?.viewers(&channel.permission_overwrites))
+  }
+
+  async fn users(&self, token: &str) -> Result<Vec<User>, DiscordError> {
+    Ok(self.guild_members(token).await?.into_iter().map(|m| m.user.into()).collect())
+  }
+
+  async fn user(&self, token: &str, user_id: &str) -> Result<User, DiscordError> {
+    let path = format!("/guilds/{}/members/{}", self.guild_id, user_id);
+    let member: GuildMember = self.send(|| self.request(token, Method::GET, &path)).await?;
+    Ok(member.user.into())
+  }
+
+  async fn bot_name(&self, token: &str) -> Result<String, DiscordError> {
+    Ok(self.me(token).await?.username)
+  }
+
+  async fn join(&self, _token: &str, _channel_id: &str) -> Result<(), DiscordError> {
+    Err(DiscordError::Unsupported("Discord bots can't join channels; give the bot's role the View Channel permission instead"))
+  }
+
+  async fn leave(&self, _token: &str, _channel_id: &str) -> Result<(), DiscordError> {
+    Err(DiscordError::Unsupported("Discord bots can't leave channels"))
+  }
+
+  async fn archive(&self, token: &str, channel_id: &str) -> Result<(), DiscordError> {
+    let category = self.guild_channels(token).await?.into_iter()
+      .find(|c| c.channel_type == GUILD_CATEGORY && c.name == self.archive_category);
+    let category_id = match category {
+      Some(category) => category.id,
+      None => {
+        let path = format!("/guilds/{}/channels", self.guild_id);
+        let body = json!({ "name": self.archive_category, "type": GUILD_CATEGORY });
+        self.send::<DiscordChannel>(|| self.request(token, Method::POST, &path).json(&body)).await?.id
+      },
+    };
+
+    // Take on the category's permissions, e.g. to make archived channels read-only.
+    let path = format!("/channels/{}", channel_id);
+    let body = json!({ "parent_id": category_id, "lock_permissions": true });
+    self.send::<DiscordChannel>(|| self.request(token, Method::PATCH, &path).json(&body)).await.map(|_| ())
+  }
+
+  async fn post(&self, token: &str, channel_id: &str, thread_id: Option<&str>, text: &str) -> Result<String, DiscordError> {
+    let content = translate_markup(text, |id| Some(format!("<#{}>", id)), |ts, token, _| Some(format!("<t:{}:{}>", ts, timestamp_style(token))));
+    let mut body = json!({
+      "content": content,
+      // Don't ping anyone mentioned in configured messages.
+      "allowed_mentions": { "parse": [] },
+    });
+    if let Some(message_id) = thread_id {
+      body["message_reference"] = json!({ "message_id": message_id });
+    }
+    let path = format!("/channels/{}/messages", channel_id);
+    let message: DiscordMessage = self.send(|| self.request(token, Method::POST, &path).json(&body)).await?;
+    Ok(message.id)
+  }
+
+  async fn open_direct_message(&self, token: &str, user_id: &str) -> Result<String, DiscordError> {
+    let body = json!({ "recipient_id": user_id });
+    let channel: DiscordChannel = self.send(|| self.request(token, Method::POST, "/users/@me/channels").json(&body)).await?;
+    Ok(channel.id)
+  }
+
+  async fn upload_file(&self, token: &str, channel_id: &str, filename: &str, content: &[u8]) -> Result<(), DiscordError> {
+    let path = format!("/channels/{}/messages", channel_id);
+    self.send::<DiscordMessage>(|| {
+      let file = Part::bytes(content.to_vec()).file_name(filename.to_string());
+      self.request(token, Method::POST, &path).multipart(Form::new().part("files[0]", file))
+    }).await.map(|_| ())
+  }
+}
+
+impl From<DiscordMessage> for Message {
+  fn from(message: DiscordMessage) -> Message {
+    // Match Slack's subtypes, so the same messages are ignored.
+    // https://discord.com/developers/docs/resources/message#message-object-message-types
+    let subtype = match message.message_type {
+      _ if message.author.bot => "bot_message",
+      4 => "channel_name",
+      6 => "pinned_item",
+      7 => "channel_join",
+      _ => "",
+    };
+    Message {
+      event_type: "message".to_string(),
+      subtype: subtype.to_string(),
+      channel: Some(message.channel_id),
+      user: Some(message.author.id),
+      text: Some(message.content),
+      ts: Some(snowflake_timestamp(&message.id)),
+    }
+  }
+}
+
+impl From<DiscordUser> for User {
+  fn from(user: DiscordUser) -> User {
+    User {
+      id: user.id,
+      name: user.username,
+      deleted: false,
+      is_bot: user.bot,
+      is_guest: false,
+    }
+  }
+}
+
+/// Discord sends permissions as a string of a bitfield.
+fn parse_permissions(permissions: &str) -> u64 {
+  permissions.parse().unwrap_or(0)
+}
+
+/// When something was created, from its id.
+/// https://discord.com/developers/docs/reference#snowflakes
+fn snowflake_timestamp(id: &str) -> Timestamp {
+  let id: u64 = id.parse().unwrap_or(0);
+  Timestamp::new(((id >> 22) as i64 + DISCORD_EPOCH) / 1000)
+}
+
+/// Discord's timestamp style closest to a Slack date token.
+/// https://discord.com/developers/docs/reference#message-formatting-timestamp-styles
+fn timestamp_style(token: &str) -> char {
+  match token {
+    "{ago}" => 'R',
+    "{date_long}" | "{date_long_pretty}" | "{date_long_full}" => 'D',
+    _ => 'd',
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  #[cfg(feature = "unit")]
+  use super::*;
+  #[cfg(feature = "unit")]
+  use crate::mock::{mock_server, SpanRecorder};
+
+  /// Test working out who can view channels from roles and permission overwrites.
+  #[test]
+  #[cfg(feature = "unit")]
+  fn test_discord_permissions() {
+    let member = |id: &str, roles: &[&str]| GuildMember {
+      user: DiscordUser { id: id.to_string(), username: id.to_string(), bot: false },
+      roles: roles.iter().map(|r| r.to_string()).collect(),
+    };
+    let overwrite = |id: &str, overwrite_type: u8, allow: u64, deny: u64| Overwrite {
+      id: id.to_string(),
+      overwrite_type,
+      allow: allow.to_string(),
+      deny: deny.to_string(),
+    };
+    let access = Access {
+      guild_id: "G".to_string(),
+      owner_id: "owner".to_string(),
+      roles: HashMap::from([
+        ("G".to_string(), VIEW_CHANNEL),
+        ("mod".to_string(), 0),
+        ("admin".to_string(), ADMINISTRATOR),
+      ]),
+      members: vec![
+        member("owner", &[]),
+        member("admin", &["admin"]),
+        member("mod", &["mod"]),
+        member("user", &[]),
+        member("banned", &[]),
+      ],
+    };
+
+    let public = vec![overwrite("banned", OVERWRITE_MEMBER, 0, VIEW_CHANNEL)];
+    assert!(!access.is_private(&public));
+    assert_eq!(access.viewers(&public), vec!["owner", "admin", "mod", "user"]);
+
+    let private = vec![
+      overwrite("G", OVERWRITE_ROLE, 0, VIEW_CHANNEL),
+      overwrite("mod", OVERWRITE_ROLE, VIEW_CHANNEL, 0),
+    ];
+    assert!(access.is_private(&private));
+    assert_eq!(access.viewers(&private), vec!["owner", "admin", "mod"]);
+  }
+
+  /// Test listing channels, reading history and archiving against a mock server.
+  #[tokio::test]
+  #[cfg(feature = "unit")]
+  async fn test_discord_channels() {
+    let (url, requests) = mock_server("", vec![
+      ("GET /users/@me", 200, r#"{"id":"100","username":"archive-bot","bot":true}"#),
+      ("GET /guilds/G", 200, r#"{"id":"G","owner_id":"1","roles":[
+        {"id":"G","permissions":"1024"},
+        {"id":"bots","permissions":"0"}
+      ]}"#),
+      ("GET /guilds/G/members", 200, r#"[
+        {"user":{"id":"1","username":"owner"},"roles":[]},
+        {"user":{"id":"2","username":"someone"},"roles":[]},
+        {"user":{"id":"100","username":"archive-bot","bot":true},"roles":["bots"]}
+      ]"#),
+      ("GET /guilds/G/channels", 200, r#"[
+        {"id":"1100000000000000000","type":0,"name":"general","parent_id":null,"permission_overwrites":[]},
+        {"id":"1100000000000000001","type":0,"name":"staff","parent_id":null,"permission_overwrites":[
+          {"id":"G","type":0,"allow":"0","deny":"1024"}
+        ]},
+        {"id":"1100000000000000002","type":2,"name":"voice","parent_id":null},
+        {"id":"1100000000000000003","type":4,"name":"Archive","parent_id":null},
+        {"id":"1100000000000000004","type":0,"name":"old","parent_id":"1100000000000000003"}
+      ]"#),
+      ("GET /channels/1100000000000000000/messages", 200, r#"[
+        {"id":"1110000000000000000","channel_id":"1100000000000000000","type":7,"author":{"id":"2","username":"someone"},"content":""},
+        {"id":"1100000000000000009","channel_id":"1100000000000000000","type":0,"author":{"id":"1","username":"owner"},"content":"hi"}
+      ]"#),
+      ("GET /channels/1100000000000000001/messages", 403, r#"{"message":"Missing Access","code":50001}"#),
+      ("PATCH /channels/1100000000000000000", 200, r#"{"id":"1100000000000000000","type":0,"name":"general","parent_id":"1100000000000000003"}"#),
+      ("POST /channels/C0/messages", 200, r#"{"id":"1200000000000000000","channel_id":"C0","type":0,"author":{"id":"100","username":"archive-bot","bot":true}}"#),
+    ]).await;
+    let mut discord = Discord::new("G");
+    discord.url = url;
+
+    let channels = discord.channels("token").await.unwrap();
+    let summary: Vec<(&str, bool, bool, i32)> = channels.iter().map(|c| (c.name.as_str(), c.is_private, c.is_member, c.num_members)).collect();
+    assert_eq!(summary, vec![("general", false, true, 3), ("staff", true, false, 1)]);
+    assert_eq!(channels[0].created, Some(snowflake_timestamp("1100000000000000000")));
+    assert_eq!(discord.members("token", "1100000000000000001").await.unwrap(), vec!["1"]);
+
+    let history = discord.history("token", "1100000000000000000", 10).await.unwrap().unwrap();
+    assert!(history[0].ignore_type());
+    assert!(!history[1].ignore_type());
+    assert!(discord.history("token", "1100000000000000001", 10).await.unwrap().is_none());
+
+    discord.archive("token", "1100000000000000000").await.unwrap();
+    assert!(discord.join("token", "1100000000000000001").await.is_err());
+    let id = discord.post("token", "C0", Some("1"), "<#1100000000000000004> has *1 member*. Last message <!date^1700000000^{date_short}|2023-11-14>.").await.unwrap();
+    assert_eq!(id, "1200000000000000000");
+
+    let requests = requests.lock().unwrap();
+    assert!(requests.contains(&r#"PATCH /channels/1100000000000000000 {"lock_permissions":true,"parent_id":"1100000000000000003"}"#.to_string()));
+    let post: serde_json::Value = requests.iter()
+      .find_map(|r| r.strip_prefix("POST /channels/C0/messages "))
+      .map(|body| serde_json::from_str(body).unwrap())
+      .unwrap();
+    assert_eq!(post["content"], "<#1100000000000000004> has **1 member**. Last message <t:1700000000:d>.");
+    assert_eq!(post["message_reference"]["message_id"], "1");
+  }
+
+  /// Keeps audit entries in memory.
+  #[cfg(feature = "unit")]
+  #[derive(Debug, Default)]
+  struct MemorySink(std::sync::Arc<Mutex<Vec<crate::AuditEntry>>>);
+
+  #[cfg(feature = "unit")]
+  impl crate::AuditSink for MemorySink {
+    fn record(&self, entry: &crate::AuditEntry) {
+      self.0.lock().unwrap().push(entry.clone());
+    }
+  }
+
+  /// Test that a run never tries to join channels, even ones the bot can't view.
+  #[tokio::test]
+  #[cfg(feature = "unit")]
+  async fn test_discord_run() {
+    let (url, _) = mock_server("", vec![
+      ("GET /users/@me", 200, r#"{"id":"100","username":"archive-bot","bot":true}"#),
+      ("GET /guilds/G", 200, r#"{"id":"G","owner_id":"1","roles":[{"id":"G","permissions":"1024"}]}"#),
+      ("GET /guilds/G/members", 200, r#"[
+        {"user":{"id":"1","username":"owner"},"roles":[]},
+        {"user":{"id":"100","username":"archive-bot","bot":true},"roles":[]}
+      ]"#),
+      ("GET /guilds/G/channels", 200, r#"[
+        {"id":"1100000000000000000","type":0,"name":"quiet","parent_id":null,"permission_overwrites":[
+          {"id":"100","type":1,"allow":"0","deny":"1024"}
+        ]}
+      ]"#),
+      ("GET /channels/1100000000000000000/messages", 403, r#"{"message":"Missing Access","code":50001}"#),
+      ("POST /channels/C0/messages", 200, r#"{"id":"1200000000000000000","channel_id":"C0","type":0,"author":{"id":"100","username":"archive-bot","bot":true}}"#),
+    ]).await;
+    let mut discord = Discord::new("G");
+    discord.url = url;
+    let entries = std::sync::Arc::new(Mutex::new(vec![]));
+    let bot = crate::ArchiveBot {
+      token: "token".to_string(),
+      notification_channel_id: "C0".to_string(),
+      audit_log: Some(Box::new(MemorySink(entries.clone()))),
+      ..crate::ArchiveBot::default()
+    }.with_platform(discord);
+
+    let report = bot.run().await.unwrap();
+    assert!(!report.channels[0].is_member && !report.channels[0].joined);
+    assert!(report.failed.is_empty());
+    let actions: Vec<crate::AuditAction> = entries.lock().unwrap().iter().map(|e| e.action).collect();
+    assert_eq!(actions, vec![crate::AuditAction::Post]);
+  }
+
+  /// Test that each request is traced in a `discord_api` span, counting rate limit retries.
+  #[tokio::test]
+  #[cfg(feature = "unit")]
+  async fn test_discord_spans() {
+    let (url, requests) = mock_server("", vec![
+      ("GET /guilds/G/channels", 429, r#"{"message":"You are being rate limited.","retry_after":0.0,"global":false}"#),
+    ]).await;
+    let mut discord = Discord::new("G");
+    discord.url = url;
+    let recorder = SpanRecorder::default();
+    let _guard = tracing::subscriber::set_default(recorder.clone());
+
+    assert!(discord.guild_channels("token").await.is_err());
+
+    assert_eq!(requests.lock().unwrap().len(), MAX_RETRIES as usize + 1);
+    let spans = recorder.spans("discord_api");
+    assert_eq!(spans.len(), 1);
+    assert!(spans[0].starts_with("discord_api method=GET path=/guilds/G/channels status=429"), "{}", spans[0]);
+    assert!(spans[0].contains(&format!(" retries={} duration_ms=", MAX_RETRIES)), "{}", spans[0]);
+  }
+}
@@ -48,6 +48,33 @@ impl<'a, E: Error> From<&'a str> for SlackError<E> {
   }
 }
 
+#[cfg(feature = "metrics")]
+impl<E: Error> SlackError<E> {
+  /// Name of the variant, e.g. `NotInChannel`, to count errors by.
+  pub(crate) fn variant(&self) -> &'static str {
+    match *self {
+      SlackError::InvalidAuth => "InvalidAuth",
+      SlackError::AccessDenied => "AccessDenied",
+      SlackError::AuthTimeout => "AuthTimeout",
+      SlackError::AuthVerification => "AuthVerification",
+      SlackError::ChannelNotFound => "ChannelNotFound",
+      SlackError::NotInChannel => "NotInChannel",
+      SlackError::IsArchived => "IsArchived",
+      SlackError::InvalidScopes => "InvalidScopes",
+      SlackError::CommentRequired => "CommentRequired",
+      SlackError::RateLimited => "RateLimited",
+      SlackError::InvalidCursor => "InvalidCursor",
+      SlackError::InvalidLimit => "InvalidLimit",
+      SlackError::InvalidType => "InvalidType",
+      SlackError::Fatal => "Fatal",
+      SlackError::Internal => "Internal",
+      SlackError::MalformedResponse(..) => "MalformedResponse",
+      SlackError::Unknown(_) => "Unknown",
+      SlackError::Client(_) => "Client",
+    }
+  }
+}
+
 impl<E: Error> fmt::Display for SlackError<E> {
   fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
     let d = match *self {
@@ -83,3 +110,111 @@ impl<E: Error + 'static> Error for SlackError<E> {
     }
   }
 }
+
+/// Error from Mattermost's REST API.
+#[derive(Debug)]
+pub enum MattermostError {
+  /// The API responded with an error status.
+  Api {
+    /// HTTP status code.
+    status: u16,
+    /// Mattermost's error id, e.g. `api.context.permissions.app_error`.
+    id: String,
+    /// Error message.
+    message: String,
+  },
+  /// The response couldn't be parsed.
+  MalformedResponse(String, serde_json::error::Error),
+  /// The request failed.
+  Client(reqwest::Error),
+}
+
+impl fmt::Display for MattermostError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match *self {
+      MattermostError::Api { status, ref message, .. } if message.is_empty() => write!(f, "HTTP {}", status),
+      MattermostError::Api { ref message, .. } => write!(f, "{}", message),
+      MattermostError::MalformedResponse(_, ref e) => write!(f, "{}", e),
+      MattermostError::Client(ref inner) => write!(f, "{}", inner),
+    }
+  }
+}
+
+impl Error for MattermostError {
+  fn source(&self) -> Option<&(dyn Error + 'static)> {
+    match *self {
+      MattermostError::MalformedResponse(_, ref e) => Some(e),
+      MattermostError::Client(ref inner) => Some(inner),
+      MattermostError::Api { .. } => None,
+    }
+  }
+}
+
+/// Error from Discord's API.
+#[derive(Debug)]
+pub enum DiscordError {
+  /// The API responded with an error status.
+  Api {
+    /// HTTP status code.
+    status: u16,
+    /// Discord's error code, e.g. `50001` for missing access.
+    code: u64,
+    /// Error message.
+    message: String,
+  },
+  /// Discord has no equivalent of the call, e.g. joining a channel.
+  Unsupported(&'static str),
+  /// The response couldn't be parsed.
+  MalformedResponse(String, serde_json::error::Error),
+  /// The request failed.
+  Client(reqwest::Error),
+}
+
+impl fmt::Display for DiscordError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match *self {
+      DiscordError::Api { status, ref message, .. } if message.is_empty() => write!(f, "HTTP {}", status),
+      DiscordError::Api { ref message, .. } => write!(f, "{}", message),
+      DiscordError::Unsupported(s) => write!(f, "{}", s),
+      DiscordError::MalformedResponse(_, ref e) => write!(f, "{}", e),
+      DiscordError::Client(ref inner) => write!(f, "{}", inner),
+    }
+  }
+}
+
+impl Error for DiscordError {
+  fn source(&self) -> Option<&(dyn Error + 'static)> {
+    match *self {
+      DiscordError::MalformedResponse(_, ref e) => Some(e),
+      DiscordError::Client(ref inner) => Some(inner),
+      _ => None,
+    }
+  }
+}
+
+/// Why a channel wasn't archived.
+#[derive(Debug)]
+pub enum ArchiveError<E> {
+  /// The channel is shared with another organization, and `shared_channels` doesn't allow archiving it.
+  Shared,
+  /// The platform's error.
+  Platform(E),
+}
+
+impl<E: fmt::Display> fmt::Display for ArchiveError<E> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match *self {
+      ArchiveError::Shared => write!(f, "Channel is shared with another organization."),
+      ArchiveError::Platform(ref inner) => write!(f, "{}", inner),
+    }
+  }
+}
+
+impl<E: Error + 'static> Error for ArchiveError<E> {
+  fn source(&self) -> Option<&(dyn Error + 'static)> {
+    match *self {
+      ArchiveError::Shared => None,
+      ArchiveError::Platform(ref inner) => Some(inner),
+    }
+  }
+}
@@ -0,0 +1,185 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use tracing::{info, warn};
+
+use crate::ArchiveBot;
+use crate::types::ChannelData;
+
+/// Format to export the analysis of every channel in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+  /// A JSON array of channels.
+  Json,
+  /// One JSON object per line, per channel.
+  JsonLines,
+  /// Comma-separated values, with a header row.
+  Csv,
+}
+
+/// Where to export the analysis of every channel, including channels that aren't mentioned in updates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Export {
+  /// Format to export in.
+  pub format: ExportFormat,
+  /// File to write to, replacing any existing file; stdout if `None`.
+  pub path: Option<&'static str>,
+}
+
+/// CSV columns, in order.
+const CSV_HEADER: [&str; 21] = [
+  "id",
+  "name",
+  "last_message_ts",
+  "last_message_relevant",
+  "num_members",
+  "created",
+  "is_old",
+  "is_small",
+  "is_new",
+  "is_ignored",
+  "is_private",
+  "is_ext_shared",
+  "is_pending_ext_shared",
+  "is_member",
+  "is_readable",
+  "history_failed",
+  "joined",
+  "join_failed",
+  "left",
+  "creator",
+  "team_id",
+];
+
+impl ExportFormat {
+  /// Write channel data in this format.
+  pub fn write<W: Write>(&self, data: &[ChannelData], mut writer: W) -> io::Result<()> {
+    match self {
+      ExportFormat::Json => {
+        serde_json::to_writer_pretty(&mut writer, data)?;
+        writeln!(writer)?;
+      },
+      ExportFormat::JsonLines => {
+        for channel in data {
+          serde_json::to_writer(&mut writer, channel)?;
+          writeln!(writer)?;
+        }
+      },
+      ExportFormat::Csv => {
+        writeln!(writer, "{}", CSV_HEADER.join(","))?;
+        for channel in data {
+          writeln!(writer, "{}", csv_row(channel))?;
+        }
+      },
+    }
+    writer.flush()
+  }
+
+  /// File extension for this format.
+  pub fn extension(&self) -> &'static str {
+    match self {
+      ExportFormat::Json => "json",
+      ExportFormat::JsonLines => "jsonl",
+      ExportFormat::Csv => "csv",
+    }
+  }
+}
+
+impl<P> ArchiveBot<P> {
+  /// Write every configured export. Failures are logged, and don't stop the run.
+  pub(crate) fn export(&self, data: &[ChannelData]) {
+    for export in &self.exports {
+      let result = match export.path {
+        Some(path) => File::create(path).and_then(|file| export.format.write(data, BufWriter::new(file))),
+        None => export.format.write(data, io::stdout().lock()),
+      };
+      match result {
+        Ok(_) => info!("Exported {:} channels to {:}", data.len(), export.path.unwrap_or("stdout")),
+        Err(e) => warn!("Unable to export channels to {:}: {:}", export.path.unwrap_or("stdout"), e),
+      }
+    }
+  }
+}
+
+/// Format a channel as a CSV row.
+fn csv_row(channel: &ChannelData) -> String {
+  [
+    csv_field(&channel.id),
+    csv_field(&channel.name),
+    channel.last_message_ts.to_string(),
+    channel.last_message_relevant.to_string(),
+    channel.num_members.to_string(),
+    channel.created.to_string(),
+    channel.is_old.to_string(),
+    channel.is_small.to_string(),
+    channel.is_new.to_string(),
+    channel.is_ignored.to_string(),
+    channel.is_private.to_string(),
+    channel.is_ext_shared.to_string(),
+    channel.is_pending_ext_shared.to_string(),
+    channel.is_member.to_string(),
+    channel.is_readable.to_string(),
+    channel.history_failed.to_string(),
+    channel.joined.to_string(),
+    channel.join_failed.to_string(),
+    channel.left.to_string(),
+    csv_field(channel.creator.as_deref().unwrap_or("")),
+    csv_field(channel.team_id.as_deref().unwrap_or("")),
+  ].join(",")
+}
+
+/// Quote a CSV field if necessary.
+fn csv_field(value: &str) -> String {
+  match value.contains([',', '"', '\n', '\r']) {
+    true => format!("\"{}\"", value.replace('"', "\"\"")),
+    false => value.to_string(),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  #[cfg(feature = "unit")]
+  use super::*;
+
+  /// Test exporting channels in each format.
+  #[test]
+  #[cfg(feature = "unit")]
+  fn test_export() {
+    let data = vec![ChannelData {
+      id: "C01".to_string(),
+      name: "quotes,\"and\" commas".to_string(),
+      last_message_ts: 1680000000,
+      last_message_relevant: true,
+      num_members: 2,
+      created: 1670000000,
+      is_old: true,
+      is_small: true,
+      is_member: true,
+      is_readable: true,
+      creator: Some("U01".to_string()),
+      team_id: Some("T01".to_string()),
+      ..ChannelData::default()
+    }];
+
+    let mut csv = vec![];
+    ExportFormat::Csv.write(&data, &mut csv).unwrap();
+    let csv = String::from_utf8(csv).unwrap();
+    let mut lines = csv.lines();
+    assert_eq!(lines.next().unwrap().split(',').count(), CSV_HEADER.len());
+    assert_eq!(lines.next().unwrap(), "C01,\"quotes,\"\"and\"\" commas\",1680000000,true,2,1670000000,true,true,false,false,false,false,false,true,true,false,false,false,false,U01,T01");
+
+    let mut jsonl = vec![];
+    ExportFormat::JsonLines.write(&data, &mut jsonl).unwrap();
+    let line: serde_json::Value = serde_json::from_slice(&jsonl).unwrap();
+    assert_eq!(line["is_ignored"], false);
+    assert_eq!(line["creator"], "U01");
+    // CSV has a column for every field.
+    let mut header = CSV_HEADER.to_vec();
+    header.sort_unstable();
+    assert_eq!(line.as_object().unwrap().keys().map(String::as_str).collect::<Vec<_>>(), header);
+
+    let mut json = vec![];
+    ExportFormat::Json.write(&data, &mut json).unwrap();
+    let array: serde_json::Value = serde_json::from_slice(&json).unwrap();
+    assert_eq!(array[0]["last_message_relevant"], true);
+  }
+}
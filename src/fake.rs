@@ -0,0 +1,172 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Mutex;
+
+use crate::platform::ChatPlatform;
+use crate::types::{Channel, Message, User};
+
+/// In-memory chat platform for tests. Set up channels, messages and users ahead of time, run the
+/// bot, then check what it did with `calls()`.
+#[derive(Debug, Default)]
+pub struct FakePlatform {
+  /// The bot's user name.
+  pub bot_name: String,
+  /// Every channel.
+  pub channels: Vec<Channel>,
+  /// Message history by channel id, newest first. Channels without history can't be read.
+  pub histories: HashMap<String, Vec<Message>>,
+  /// Member ids by channel id.
+  pub members: HashMap<String, Vec<String>>,
+  /// Every user.
+  pub users: Vec<User>,
+  /// Whether history can only be read in channels the bot is a member of or has joined, as on Slack.
+  pub history_needs_membership: bool,
+  /// Calls that fail, by method name, e.g. `"join"`. `"reply"` fails only posts in a thread.
+  pub failing: Vec<&'static str>,
+  calls: Mutex<Vec<FakeCall>>,
+}
+
+/// A call that changed something, recorded by `FakePlatform`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FakeCall {
+  /// Joined a channel.
+  Join(String),
+  /// Left a channel.
+  Leave(String),
+  /// Archived a channel.
+  Archive(String),
+  /// Posted a message.
+  Post {
+    /// Channel id.
+    channel_id: String,
+    /// Id of the message replied to, if any.
+    thread_id: Option<String>,
+    /// Message text.
+    text: String,
+  },
+  /// Opened a direct message conversation with a user.
+  OpenDirectMessage(String),
+  /// Uploaded a file.
+  Upload {
+    /// Channel id.
+    channel_id: String,
+    /// File name.
+    filename: String,
+    /// File contents.
+    content: Vec<u8>,
+  },
+}
+
+/// Error returned by `FakePlatform` calls that are set to fail, or for unknown ids.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FakeError(pub &'static str);
+
+impl fmt::Display for FakeError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}", self.0)
+  }
+}
+
+impl std::error::Error for FakeError {}
+
+impl FakePlatform {
+  /// Calls that changed something, in order.
+  pub fn calls(&self) -> Vec<FakeCall> {
+    self.calls.lock().unwrap().clone()
+  }
+
+  /// Whether the bot is a member of a channel, or has joined it.
+  fn is_member(&self, channel_id: &str) -> bool {
+    self.channels.iter().any(|c| c.id == channel_id && c.is_member)
+      || self.calls().contains(&FakeCall::Join(channel_id.to_string()))
+  }
+
+  /// Fail if the method is set to fail.
+  fn check(&self, method: &'static str) -> Result<(), FakeError> {
+    match self.failing.contains(&method) {
+      true => Err(FakeError(method)),
+      false => Ok(()),
+    }
+  }
+
+  /// Record a call, if it doesn't fail. Returns the call's id.
+  fn record(&self, method: &'static str, call: FakeCall) -> Result<String, FakeError> {
+    self.check(method)?;
+    let mut calls = self.calls.lock().unwrap();
+    calls.push(call);
+    Ok(calls.len().to_string())
+  }
+}
+
+impl ChatPlatform for FakePlatform {
+  type Error = FakeError;
+
+  async fn channels(&self, _token: &str) -> Result<Vec<Channel>, FakeError> {
+    self.check("channels")?;
+    Ok(self.channels.clone())
+  }
+
+  async fn history(&self, _token: &str, channel_id: &str, limit: u16) -> Result<Option<Vec<Message>>, FakeError> {
+    self.check("history")?;
+    if self.history_needs_membership && !self.is_member(channel_id) {
+      return Ok(None);
+    }
+    Ok(self.histories.get(channel_id).map(|h| h.iter().take(limit as usize).cloned().collect()))
+  }
+
+  async fn members(&self, _token: &str, channel_id: &str) -> Result<Vec<String>, FakeError> {
+    self.check("members")?;
+    self.members.get(channel_id).cloned().ok_or(FakeError("channel_not_found"))
+  }
+
+  async fn users(&self, _token: &str) -> Result<Vec<User>, FakeError> {
+    self.check("users")?;
+    Ok(self.users.clone())
+  }
+
+  async fn user(&self, _token: &str, user_id: &str) -> Result<User, FakeError> {
+    self.check("user")?;
+    self.users.iter().find(|u| u.id == user_id).cloned().ok_or(FakeError("user_not_found"))
+  }
+
+  async fn bot_name(&self, _token: &str) -> Result<String, FakeError> {
+    self.check("bot_name")?;
+    Ok(self.bot_name.clone())
+  }
+
+  async fn join(&self, _token: &str, channel_id: &str) -> Result<(), FakeError> {
+    self.record("join", FakeCall::Join(channel_id.to_string())).map(|_| ())
+  }
+
+  async fn leave(&self, _token: &str, channel_id: &str) -> Result<(), FakeError> {
+    self.record("leave", FakeCall::Leave(channel_id.to_string())).map(|_| ())
+  }
+
+  async fn archive(&self, _token: &str, channel_id: &str) -> Result<(), FakeError> {
+    self.record("archive", FakeCall::Archive(channel_id.to_string())).map(|_| ())
+  }
+
+  async fn post(&self, _token: &str, channel_id: &str, thread_id: Option<&str>, text: &str) -> Result<String, FakeError> {
+    if thread_id.is_some() {
+      self.check("reply")?;
+    }
+    self.record("post", FakeCall::Post {
+      channel_id: channel_id.to_string(),
+      thread_id: thread_id.map(String::from),
+      text: text.to_string(),
+    })
+  }
+
+  async fn open_direct_message(&self, _token: &str, user_id: &str) -> Result<String, FakeError> {
+    self.record("open_direct_message", FakeCall::OpenDirectMessage(user_id.to_string()))?;
+    Ok(format!("D_{}", user_id))
+  }
+
+  async fn upload_file(&self, _token: &str, channel_id: &str, filename: &str, content: &[u8]) -> Result<(), FakeError> {
+    self.record("upload_file", FakeCall::Upload {
+      channel_id: channel_id.to_string(),
+      filename: filename.to_string(),
+      content: content.to_vec(),
+    }).map(|_| ())
+  }
+}
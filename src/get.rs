@@ -1,88 +1,94 @@
-use log::{debug, warn};
+use std::collections::HashSet;
+use tracing::{debug, warn};
 
 use crate::ArchiveBot;
+use crate::platform::ChatPlatform;
 use crate::types::*;
-use crate::error::SlackError;
 
-impl ArchiveBot {
+impl<P: ChatPlatform> ArchiveBot<P> {
   /// Get a comprehensive list of basic channel data.
   pub async fn get_channels(&self) -> Vec<Channel> {
-    let mut channels: Vec<Channel> = vec![];
-    let mut cursor: String = "".to_string();
-    loop {
-      let (more_channels, next_cursor) = self.get_channel_data(cursor).await;
-      channels.extend(more_channels);
-      if next_cursor == "" {
-        break;
-      }
-      cursor = next_cursor;
-    }
-    debug!("{} channels found", channels.len());
+    self.try_get_channels().await.unwrap_or_default()
+  }
 
-    channels
+  /// Get a comprehensive list of basic channel data, or why it couldn't be listed.
+  pub(crate) async fn try_get_channels(&self) -> Result<Vec<Channel>, P::Error> {
+    match self.platform.channels(&self.token).await {
+      Ok(channels) => {
+        debug!(count = channels.len(), "Listed channels");
+        Ok(channels)
+      },
+      Err(err) => {
+        warn!(error = %err, "Unable to list channels");
+        Err(err)
+      },
+    }
   }
 
-  /// Get channel data for given cursor (pagination).
-  async fn get_channel_data(&self, cursor: String) -> (Vec<Channel>, String) {
-    let mut params: UrlParams = vec![
-      ("exclude_archived", String::from("1")),
-      ("exclude_members", String::from("0")),
-      ("limit", String::from("1000")),
-      ("types", String::from("public_channel,private_channel")),
-    ];
-    if cursor != "" {
-      params.push(("cursor", cursor));
+  /// Get the bot's own user name, e.g. to suggest inviting it to a channel.
+  pub async fn get_bot_name(&self) -> Option<String> {
+    match self.platform.bot_name(&self.token).await {
+      Ok(name) => Some(name),
+      Err(err) => {
+        warn!(error = %err, "Unable to identify bot user");
+        None
+      },
     }
+  }
 
-    let response = self.send("conversations.list", &mut params).await;
+  /// Get a user's info.
+  pub async fn get_user(&self, user_id: &str) -> Option<User> {
+    match self.platform.user(&self.token, user_id).await {
+      Ok(user) => Some(user),
+      Err(err) => {
+        warn!(user_id, error = %err, "Unable to fetch user");
+        None
+      },
+    }
+  }
 
-    match response {
-      Ok(resp) => {
-        let cursor = match resp.response_metadata {
-          Some(metadata) => metadata.next_cursor,
-          None => "".into(),
-        };
-        if let Some(channels) = resp.channels {
-          return (channels, cursor);
-        }
+  /// Get the ids of all active, human users in the workspace.
+  pub async fn get_human_user_ids(&self) -> Option<HashSet<String>> {
+    match self.platform.users(&self.token).await {
+      Ok(users) => {
+        let ids: HashSet<String> = users.into_iter().filter(User::is_active_human).map(|user| user.id).collect();
+        debug!(count = ids.len(), "Listed active human users");
+        Some(ids)
       },
       Err(err) => {
-        warn!("Unable to list channels: {:}", err);
+        warn!(error = %err, "Unable to list users");
+        None
       },
     }
-
-    (vec![], "".to_string())
   }
 
-  /// Get conversation history for.
-  pub async fn get_history(&self, channel_id: &str, limit: u16) -> Option<Vec<Message>> {
-    let mut params: UrlParams = vec![
-      ("channel", channel_id.to_string()),
-      ("limit", limit.to_string()),
-    ];
-
-    let response: Result<SlackResponse, SlackError<reqwest::Error>> = self.send("conversations.history", &mut params).await;
+  /// Get the ids of a channel's members.
+  pub async fn get_members(&self, channel_id: &str) -> Option<Vec<String>> {
+    match self.platform.members(&self.token, channel_id).await {
+      Ok(members) => Some(members),
+      Err(err) => {
+        warn!(channel_id, error = %err, "Unable to list members");
+        None
+      },
+    }
+  }
 
-    match response {
-      Ok(list) => {return list.messages},
-      Err(err) => match err {
-        SlackError::NotInChannel => {
-          warn!("Not in channel {:}", channel_id);
-          None
-        },
-        _ => {
-          warn!("Unable to fetch channel history: {:}", err);
-          None
-        },
-      }
+  /// Get conversation history for. `Ok(None)` if the bot isn't in the channel.
+  /// Not being in the channel is only a warning if the bot should be, i.e. `expect_member`.
+  pub async fn get_history(&self, channel_id: &str, limit: u16, expect_member: bool) -> Result<Option<Vec<Message>>, P::Error> {
+    let history = self.platform.history(&self.token, channel_id, limit).await;
+    match &history {
+      Ok(Some(_)) => {},
+      Ok(None) if expect_member => warn!(channel_id, "Not in channel"),
+      Ok(None) => debug!(channel_id, "Not in channel"),
+      Err(err) => warn!(channel_id, error = %err, "Unable to fetch channel history"),
     }
+    history
   }
 }
 
 #[cfg(test)]
 mod tests {
-  #[cfg(feature="unit_output")]
-  use simplelog;
 
   /// Create a test message and print it to stdout rather than posting to Slack.
   #[tokio::test]
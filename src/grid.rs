@@ -0,0 +1,226 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+
+use crate::error::SlackError;
+use crate::platform::ChatPlatform;
+use crate::slack::Slack;
+use crate::types::{AdminConversation, Channel, Message, Timestamp, UrlParams, User};
+
+/// Largest page `admin.conversations.search` returns.
+const SEARCH_LIMIT: usize = 20;
+
+/// Slack's Web API on Enterprise Grid, with an org-level admin token. Manages every workspace's
+/// channels at once, for one report across the organization.
+///
+/// The token is an org admin's user token with the `admin.conversations:read` and
+/// `admin.conversations:write` scopes. Channels are listed with `admin.conversations.search` and
+/// archived with `admin.conversations.archive`, so they're never joined. Channels whose history
+/// can't be read are judged by their last activity. Each channel's workspace is kept in
+/// `ChannelData::team_id`; org-shared channels are listed once, under their host workspace.
+#[derive(Debug, Default)]
+pub struct EnterpriseGrid {
+  /// Workspaces whose channels to manage. Every workspace in the organization if empty.
+  pub team_ids: Vec<&'static str>,
+  /// Bot token to post messages and upload files with, so they don't come from the admin.
+  /// The admin token is used if `None`.
+  pub bot_token: Option<String>,
+  /// Last activity by channel id, from the search, for channels whose history can't be read.
+  last_activity: Mutex<HashMap<String, i64>>,
+}
+
+impl EnterpriseGrid {
+  /// Manage channels in these workspaces, or every workspace in the organization if empty.
+  pub fn new(team_ids: Vec<&'static str>) -> EnterpriseGrid {
+    EnterpriseGrid {
+      team_ids,
+      ..EnterpriseGrid::default()
+    }
+  }
+
+  /// Token to post as.
+  fn post_token<'a>(&'a self, token: &'a str) -> &'a str {
+    self.bot_token.as_deref().unwrap_or(token)
+  }
+}
+
+/// Seconds since the epoch, from a timestamp in seconds, milliseconds or microseconds.
+/// The `admin.*` methods aren't consistent about which they return.
+fn normalize_timestamp(t: i64) -> i64 {
+  match t {
+    t if t >= 100_000_000_000_000 => t / 1_000_000,
+    t if t >= 100_000_000_000 => t / 1_000,
+    t => t,
+  }
+}
+
+impl From<AdminConversation> for Channel {
+  fn from(conversation: AdminConversation) -> Channel {
+    let created = normalize_timestamp(conversation.created);
+    Channel {
+      id: conversation.id,
+      name: conversation.name,
+      is_channel: !conversation.is_private,
+      created: match created {
+        0 => None,
+        t => Some(Timestamp::new(t)),
+      },
+      creator: conversation.creator_id,
+      is_general: conversation.is_general,
+      is_shared: conversation.is_org_shared || conversation.is_ext_shared,
+      is_ext_shared: conversation.is_ext_shared,
+      is_org_shared: conversation.is_org_shared,
+      is_pending_ext_shared: conversation.is_pending_ext_shared,
+      is_private: conversation.is_private,
+      num_members: conversation.member_count,
+      team_id: conversation.conversation_host_id.or(conversation.context_team_id),
+      ..Channel::default()
+    }
+  }
+}
+
+impl ChatPlatform for EnterpriseGrid {
+  type Error = SlackError<reqwest::Error>;
+
+  const READS_WITHOUT_JOINING: bool = true;
+
+  fn validate_token(&self, token: &str) -> Result<(), String> {
+    match token.starts_with("xoxp-") {
+      true => Ok(()),
+      false => Err("token is not an org admin's user token".to_string()),
+    }
+  }
+
+  async fn channels(&self, token: &str) -> Result<Vec<Channel>, Self::Error> {
+    let mut channels: Vec<Channel> = vec![];
+    let mut seen = HashSet::new();
+    let mut cursor = String::new();
+    loop {
+      let mut params: UrlParams = vec![
+        ("search_channel_types", String::from("exclude_archived")),
+        ("limit", SEARCH_LIMIT.to_string()),
+      ];
+      if !self.team_ids.is_empty() {
+        params.push(("team_ids", self.team_ids.join(",")));
+      }
+      if !cursor.is_empty() {
+        params.push(("cursor", cursor));
+      }
+
+      let response = Slack.send(token, "admin.conversations.search", &mut params).await?;
+      cursor = response.next_cursor.unwrap_or_default();
+      let mut last_activity = self.last_activity.lock().unwrap();
+      for conversation in response.conversations.unwrap_or_default() {
+        // Org-shared channels show up once for each workspace they're in.
+        if !seen.insert(conversation.id.clone()) {
+          continue;
+        }
+        last_activity.insert(conversation.id.clone(), normalize_timestamp(conversation.last_activity_ts));
+        channels.push(conversation.into());
+      }
+      if cursor.is_empty() {
+        break;
+      }
+    }
+    Ok(channels)
+  }
+
+  async fn history(&self, token: &str, channel_id: &str, limit: u16) -> Result<Option<Vec<Message>>, Self::Error> {
+    match Slack.history(token, channel_id, limit).await {
+      Ok(Some(messages)) => return Ok(Some(messages)),
+      Ok(None) | Err(SlackError::NotInChannel | SlackError::ChannelNotFound) => {},
+      Err(e) => return Err(e),
+    }
+
+    // The admin can't read this channel, so go by when it was last active.
+    let last_activity = self.last_activity.lock().unwrap().get(channel_id).copied().unwrap_or(0);
+    Ok(Some(match last_activity {
+      0 => vec![],
+      t => vec![Message {
+        event_type: "message".to_string(),
+        channel: Some(channel_id.to_string()),
+        ts: Some(Timestamp::new(t)),
+        ..Message::default()
+      }],
+    }))
+  }
+
+  async fn members(&self, token: &str, channel_id: &str) -> Result<Vec<String>, Self::Error> {
+    Slack.members(token, channel_id).await
+  }
+
+  async fn users(&self, token: &str) -> Result<Vec<User>, Self::Error> {
+    Slack.users(token).await
+  }
+
+  async fn user(&self, token: &str, user_id: &str) -> Result<User, Self::Error> {
+    Slack.user(token, user_id).await
+  }
+
+  async fn bot_name(&self, token: &str) -> Result<String, Self::Error> {
+    Slack.bot_name(self.post_token(token)).await
+  }
+
+  async fn join(&self, token: &str, channel_id: &str) -> Result<(), Self::Error> {
+    Slack.join(self.post_token(token), channel_id).await
+  }
+
+  async fn leave(&self, token: &str, channel_id: &str) -> Result<(), Self::Error> {
+    Slack.leave(self.post_token(token), channel_id).await
+  }
+
+  async fn archive(&self, token: &str, channel_id: &str) -> Result<(), Self::Error> {
+    let mut params: UrlParams = vec![
+      ("channel_id", channel_id.to_string()),
+    ];
+    Slack.send(token, "admin.conversations.archive", &mut params).await.map(|_| ())
+  }
+
+  async fn post(&self, token: &str, channel_id: &str, thread_id: Option<&str>, text: &str) -> Result<String, Self::Error> {
+    Slack.post(self.post_token(token), channel_id, thread_id, text).await
+  }
+
+  async fn open_direct_message(&self, token: &str, user_id: &str) -> Result<String, Self::Error> {
+    Slack.open_direct_message(self.post_token(token), user_id).await
+  }
+
+  async fn upload_file(&self, token: &str, channel_id: &str, filename: &str, content: &[u8]) -> Result<(), Self::Error> {
+    Slack.upload_file(self.post_token(token), channel_id, filename, content).await
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  #[cfg(feature = "unit")]
+  use super::*;
+
+  /// Test reading channels from `admin.conversations.search`.
+  #[test]
+  #[cfg(feature = "unit")]
+  fn test_grid_channels() {
+    assert_eq!(normalize_timestamp(1_700_000_000), 1_700_000_000);
+    assert_eq!(normalize_timestamp(1_700_000_000_123), 1_700_000_000);
+    assert_eq!(normalize_timestamp(1_700_000_000_123_456), 1_700_000_000);
+    assert_eq!(normalize_timestamp(0), 0);
+
+    let response: crate::types::SlackResponse = serde_json::from_str(r#"{
+      "ok": true,
+      "conversations": [
+        {"id": "C01", "name": "general", "is_general": true, "member_count": 40, "created": 1600000000000, "context_team_id": "T01"},
+        {"id": "C02", "name": "org-wide", "is_org_shared": true, "conversation_host_id": "T02", "context_team_id": "T01", "last_activity_ts": 1700000000123456},
+        {"id": "C03", "name": "secret", "is_private": true, "creator_id": "U01", "context_team_id": "T03"}
+      ],
+      "next_cursor": ""
+    }"#).unwrap();
+    let channels: Vec<Channel> = response.conversations.unwrap().into_iter().map(Channel::from).collect();
+
+    assert_eq!(channels[0].team_id.as_deref(), Some("T01"));
+    assert!(channels[0].is_general && channels[0].is_channel);
+    assert_eq!(channels[0].num_members, 40);
+    assert_eq!(channels[0].created, Some(Timestamp::new(1_600_000_000)));
+    assert_eq!(channels[1].team_id.as_deref(), Some("T02"));
+    assert!(channels[1].is_org_shared && channels[1].is_shared && !channels[1].is_ext_shared);
+    assert!(channels[2].is_private && !channels[2].is_channel);
+    assert_eq!(channels[2].creator.as_deref(), Some("U01"));
+    assert_eq!(channels[2].created, None);
+  }
+}
@@ -0,0 +1,335 @@
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use chrono::NaiveDateTime;
+use tracing::{info, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::ArchiveBot;
+use crate::platform::ChatPlatform;
+use crate::types::{ChannelData, Section};
+
+/// Where to write a standalone HTML report of every channel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HtmlReport {
+  /// File to write the report to, replacing any existing file.
+  pub path: &'static str,
+  /// JSON Lines file to keep a summary of each run in, for trend charts. Appended to on every run
+  /// except dry runs. No trends are charted if `None`.
+  pub history_path: Option<&'static str>,
+}
+
+/// Summary of a run, kept for trend charts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+struct Snapshot {
+  timestamp: i64,
+  channels: usize,
+  stale: usize,
+  small: usize,
+  stale_and_small: usize,
+  #[serde(default)]
+  shared: usize,
+  #[serde(default)]
+  pending_shared: usize,
+  private: usize,
+}
+
+/// Bucket upper bounds (inclusive) and labels for the last activity histogram, in days.
+const ACTIVITY_BUCKETS: [(i64, &str); 6] = [
+  (7, "< 1 week"),
+  (30, "1-4 weeks"),
+  (90, "1-3 months"),
+  (180, "3-6 months"),
+  (365, "6-12 months"),
+  (i64::MAX, "> 1 year"),
+];
+
+/// Bucket upper bounds (inclusive) and labels for the member count histogram.
+const MEMBER_BUCKETS: [(i32, &str); 6] = [
+  (0, "0"),
+  (1, "1"),
+  (3, "2-3"),
+  (10, "4-10"),
+  (50, "11-50"),
+  (i32::MAX, "51+"),
+];
+
+/// A line on a trend chart: name, color, and the value for each run.
+type Series<'s> = (&'s str, &'s str, fn(&Snapshot) -> usize);
+
+const CHART_WIDTH: usize = 480;
+const CHART_HEIGHT: usize = 200;
+
+impl<P: ChatPlatform> ArchiveBot<P> {
+  /// Write the HTML report, if configured, updating its history. Failures are logged, and don't stop the run.
+  pub(crate) fn write_html_report(&self, data: &[ChannelData]) {
+    let report = match &self.html_report {
+      Some(report) => report,
+      None => return,
+    };
+    let now = chrono::offset::Utc::now().timestamp();
+
+    let mut history = match report.history_path {
+      Some(path) => read_history(path),
+      None => vec![],
+    };
+    let snapshot = self.snapshot(data, now);
+    if let (Some(path), false) = (report.history_path, self.dry_run) {
+      if let Err(e) = append_history(path, &snapshot) {
+        warn!("Unable to update report history {:}: {:}", path, e);
+      }
+    }
+    history.push(snapshot);
+
+    match fs::write(report.path, self.render_html_report(data, &history, now)) {
+      Ok(_) => info!("Wrote HTML report to {:}", report.path),
+      Err(e) => warn!("Unable to write HTML report to {:}: {:}", report.path, e),
+    }
+  }
+
+  /// Summarize channels by section.
+  fn snapshot(&self, data: &[ChannelData], timestamp: i64) -> Snapshot {
+    let count = |section: Section| data.iter().filter(|c| self.channel_section(c) == Some(section)).count();
+    Snapshot {
+      timestamp,
+      channels: data.iter().filter(|c| !c.is_ignored).count(),
+      stale: count(Section::Stale),
+      small: count(Section::Small),
+      stale_and_small: count(Section::StaleAndSmall),
+      shared: count(Section::Shared),
+      pending_shared: count(Section::PendingShared),
+      private: count(Section::Private),
+    }
+  }
+
+  /// Render the report as a standalone HTML page.
+  fn render_html_report(&self, data: &[ChannelData], history: &[Snapshot], now: i64) -> String {
+    let c = self.catalog(&self.notification_channel_id);
+    let channels: Vec<&ChannelData> = data.iter().filter(|c| !c.is_ignored).collect();
+
+    let mut activity = vec![0; ACTIVITY_BUCKETS.len()];
+    let mut never = 0;
+    let mut members = vec![0; MEMBER_BUCKETS.len()];
+    for channel in &channels {
+      match channel.last_activity() {
+        0 => never += 1,
+        ts => {
+          let days = (now - ts) / (24 * 60 * 60);
+          if let Some(i) = ACTIVITY_BUCKETS.iter().position(|(max, _)| days <= *max) {
+            activity[i] += 1;
+          }
+        },
+      }
+      if let Some(i) = MEMBER_BUCKETS.iter().position(|(max, _)| channel.num_members <= *max) {
+        members[i] += 1;
+      }
+    }
+    let mut activity: Vec<(&str, usize)> = ACTIVITY_BUCKETS.iter().map(|(_, label)| *label).zip(activity).collect();
+    activity.push(("Unknown", never));
+    let members: Vec<(&str, usize)> = MEMBER_BUCKETS.iter().map(|(_, label)| *label).zip(members).collect();
+
+    let mut html = String::new();
+    html.push_str("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>Workspace health</title>\n");
+    html.push_str(STYLE);
+    html.push_str("</head>\n<body>\n<h1>Workspace health</h1>\n");
+    html.push_str(&format!("<p>{} channels, generated {}.</p>\n", channels.len(), format_date(now)));
+
+    html.push_str("<div class=\"charts\">\n");
+    html.push_str(&bar_chart("Last activity", &activity));
+    html.push_str(&bar_chart("Members", &members));
+    if history.len() > 1 {
+      html.push_str(&line_chart("Flagged channels over time", history, &[
+        (c.section_stale, "#e8a33d", |s| s.stale),
+        (c.section_small, "#4c8bd6", |s| s.small),
+        (c.section_stale_and_small, "#d6554c", |s| s.stale_and_small),
+        (c.section_shared, "#8e5cc4", |s| s.shared),
+        (c.section_pending_shared, "#3da58a", |s| s.pending_shared),
+        (c.section_private, "#888888", |s| s.private),
+      ]));
+    }
+    html.push_str("</div>\n");
+
+    html.push_str("<table>\n<thead><tr><th>Channel</th><th>Members</th><th>Last activity</th><th>Created</th><th>Flagged</th></tr></thead>\n<tbody>\n");
+    for channel in channels {
+      let section = self.channel_section(channel).map(|s| s.title(c)).unwrap_or("");
+      html.push_str(&format!(
+        "<tr><td>{}{}</td><td>{}</td><td data-sort=\"{}\">{}</td><td data-sort=\"{}\">{}</td><td>{}</td></tr>\n",
+        if channel.is_private { "🔒 " } else { "#" },
+        escape(&channel.name),
+        channel.num_members,
+        channel.last_activity(), format_date(channel.last_activity()),
+        channel.created, format_date(channel.created),
+        escape(section),
+      ));
+    }
+    html.push_str("</tbody>\n</table>\n");
+    html.push_str(SCRIPT);
+    html.push_str("</body>\n</html>\n");
+    html
+  }
+}
+
+/// Read the summaries of previous runs, skipping any lines that can't be parsed.
+fn read_history(path: &str) -> Vec<Snapshot> {
+  match fs::read_to_string(path) {
+    Ok(contents) => contents.lines().filter_map(|line| serde_json::from_str(line).ok()).collect(),
+    Err(e) if e.kind() == io::ErrorKind::NotFound => vec![],
+    Err(e) => {
+      warn!("Unable to read report history {:}: {:}", path, e);
+      vec![]
+    },
+  }
+}
+
+/// Append the summary of this run to the history.
+fn append_history(path: &str, snapshot: &Snapshot) -> io::Result<()> {
+  let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+  writeln!(file, "{}", serde_json::to_string(snapshot)?)
+}
+
+/// Render an SVG bar chart.
+fn bar_chart(title: &str, bars: &[(&str, usize)]) -> String {
+  let max = bars.iter().map(|(_, n)| *n).max().unwrap_or(0).max(1);
+  let slot = CHART_WIDTH / bars.len().max(1);
+  let plot_height = CHART_HEIGHT - 40;
+  let mut svg = format!("<figure><figcaption>{}</figcaption><svg viewBox=\"0 0 {} {}\" role=\"img\">", escape(title), CHART_WIDTH, CHART_HEIGHT);
+  for (i, (label, n)) in bars.iter().enumerate() {
+    let height = n * plot_height / max;
+    let x = i * slot + 4;
+    let y = 20 + plot_height - height;
+    svg.push_str(&format!(
+      "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\"><title>{}: {}</title></rect>\
+      <text x=\"{}\" y=\"{}\" class=\"value\">{}</text>\
+      <text x=\"{}\" y=\"{}\">{}</text>",
+      x, y, slot - 8, height, escape(label), n,
+      x + (slot - 8) / 2, y - 4, n,
+      x + (slot - 8) / 2, CHART_HEIGHT - 4, escape(label),
+    ));
+  }
+  svg.push_str("</svg></figure>\n");
+  svg
+}
+
+/// Render an SVG line chart of one or more series over previous runs.
+fn line_chart(title: &str, history: &[Snapshot], series: &[Series]) -> String {
+  let max = history.iter().flat_map(|s| series.iter().map(move |(_, _, value)| value(s))).max().unwrap_or(0).max(1);
+  let plot_height = CHART_HEIGHT - 40;
+  let step = (CHART_WIDTH - 8) as f64 / (history.len() - 1).max(1) as f64;
+  let mut svg = format!("<figure><figcaption>{}</figcaption><svg viewBox=\"0 0 {} {}\" role=\"img\">", escape(title), CHART_WIDTH, CHART_HEIGHT);
+  for (name, color, value) in series {
+    let points: Vec<String> = history.iter().enumerate()
+      .map(|(i, s)| format!("{:.1},{}", 4.0 + i as f64 * step, 20 + plot_height - value(s) * plot_height / max))
+      .collect();
+    svg.push_str(&format!(
+      "<polyline points=\"{}\" stroke=\"{}\"><title>{}</title></polyline>",
+      points.join(" "), color, escape(name),
+    ));
+  }
+  let (first, last) = (history[0].timestamp, history[history.len() - 1].timestamp);
+  svg.push_str(&format!(
+    "<text x=\"4\" y=\"{}\" class=\"start\">{}</text><text x=\"{}\" y=\"{}\" class=\"end\">{}</text></svg>",
+    CHART_HEIGHT - 4, format_date(first), CHART_WIDTH - 4, CHART_HEIGHT - 4, format_date(last),
+  ));
+  let legend: Vec<String> = series.iter()
+    .map(|(name, color, _)| format!("<span style=\"color:{}\">■</span> {}", color, escape(name)))
+    .collect();
+  svg.push_str(&format!("<p class=\"legend\">{}</p></figure>\n", legend.join(" ")));
+  svg
+}
+
+/// Format a timestamp as a date, or an empty string if unknown.
+fn format_date(ts: i64) -> String {
+  match NaiveDateTime::from_timestamp_opt(ts, 0) {
+    Some(date) if ts > 0 => date.format("%Y-%m-%d").to_string(),
+    _ => "".to_string(),
+  }
+}
+
+/// Escape text for HTML.
+fn escape(text: &str) -> String {
+  text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+const STYLE: &str = "<style>
+body { font-family: system-ui, sans-serif; margin: 2rem; color: #222; }
+.charts { display: flex; flex-wrap: wrap; gap: 2rem; }
+figure { margin: 0; width: 480px; }
+figcaption { font-weight: bold; margin-bottom: .5rem; }
+rect { fill: #4c8bd6; }
+polyline { fill: none; stroke-width: 2; }
+text { font-size: 11px; text-anchor: middle; }
+text.start { text-anchor: start; }
+text.end { text-anchor: end; }
+.legend { font-size: 13px; }
+table { border-collapse: collapse; margin-top: 2rem; }
+th, td { padding: .25rem .75rem; border-bottom: 1px solid #ddd; text-align: left; }
+th { cursor: pointer; user-select: none; }
+th.asc::after { content: ' ▲'; }
+th.desc::after { content: ' ▼'; }
+</style>
+";
+
+/// Sort the table by a column when its heading is clicked.
+const SCRIPT: &str = "<script>
+document.querySelectorAll('th').forEach((th, column) => th.addEventListener('click', () => {
+  const tbody = th.closest('table').querySelector('tbody');
+  const asc = !th.classList.contains('asc');
+  th.parentNode.querySelectorAll('th').forEach(h => h.classList.remove('asc', 'desc'));
+  th.classList.add(asc ? 'asc' : 'desc');
+  const key = row => {
+    const cell = row.children[column];
+    const value = cell.dataset.sort ?? cell.textContent;
+    return isNaN(value) || value === '' ? value.toLowerCase() : Number(value);
+  };
+  [...tbody.rows]
+    .sort((a, b) => (key(a) > key(b) ? 1 : key(a) < key(b) ? -1 : 0) * (asc ? 1 : -1))
+    .forEach(row => tbody.appendChild(row));
+}));
+</script>
+";
+
+#[cfg(test)]
+mod tests {
+  #[cfg(feature = "unit")]
+  use super::*;
+
+  /// Test rendering the HTML report.
+  #[test]
+  #[cfg(feature = "unit")]
+  fn test_html_report() {
+    let now = 1700000000;
+    let channel = |name: &str, ts: i64, members: i32, is_old: bool, is_ignored: bool| ChannelData {
+      id: format!("C_{}", name),
+      name: name.to_string(),
+      last_message_ts: ts,
+      last_message_relevant: true,
+      num_members: members,
+      is_old,
+      is_small: members <= 3,
+      is_ignored,
+      is_member: true,
+      is_readable: true,
+      ..ChannelData::default()
+    };
+    let data = vec![
+      channel("<script>", now - 400 * 24 * 60 * 60, 2, true, false),
+      channel("busy", now - 60 * 60, 40, false, false),
+      channel("-ignored", now, 1, false, true),
+    ];
+    let bot = ArchiveBot::default();
+
+    let snapshot = bot.snapshot(&data, now);
+    assert_eq!((snapshot.channels, snapshot.stale_and_small, snapshot.stale), (2, 1, 0));
+
+    let html = bot.render_html_report(&data, &[snapshot], now);
+    assert!(html.contains("#&lt;script&gt;"));
+    assert!(!html.contains("ignored"));
+    assert!(html.contains("<title>&gt; 1 year: 1</title>"));
+    assert!(html.contains("<title>11-50: 1</title>"));
+    assert!(!html.contains("over time"));
+
+    let earlier = Snapshot { timestamp: now - 7 * 24 * 60 * 60, ..snapshot };
+    let html = bot.render_html_report(&data, &[earlier, snapshot], now);
+    assert!(html.contains("Flagged channels over time"));
+    assert_eq!(html.matches("<polyline").count(), 6);
+  }
+}
@@ -8,43 +8,296 @@
 //! Archive Bot.
 
 use std::env;
-use log::info;
-use rand::seq::SliceRandom;
-use chrono;
+use std::collections::{BTreeMap, HashSet};
+use chrono::format::{Item, StrftimeItems};
+use chrono_tz::Tz;
+use tracing::{debug, field, info, info_span, Instrument};
 use futures::future;
 
+mod audit;
 mod client;
+#[cfg(feature = "daemon")]
+mod daemon;
+mod discord;
 mod error;
+mod export;
 mod get;
+mod grid;
+mod html;
+mod locale;
+mod mattermost;
+mod message;
+#[cfg(feature = "metrics")]
+mod metrics;
+#[cfg(all(test, feature = "unit"))]
+mod mock;
+mod platform;
 mod post;
+mod rate_limit;
+mod slack;
+mod template;
 mod types;
+mod workspaces;
+#[cfg(feature = "fake")]
+mod fake;
 
 use types::*;
+pub use types::{Channel, ChannelData, Message, Timestamp, User};
+pub use platform::ChatPlatform;
+pub use audit::{AuditAction, AuditEntry, AuditFile, AuditSink};
+pub use rate_limit::RateLimited;
+pub use workspaces::{CombinedSummary, Workspace, WorkspaceReport, Workspaces, WorkspacesReport};
+pub use slack::Slack;
+pub use mattermost::Mattermost;
+pub use discord::Discord;
+pub use grid::EnterpriseGrid;
+pub use error::{ArchiveError, DiscordError, MattermostError};
+#[cfg(feature = "fake")]
+pub use fake::{FakeCall, FakeError, FakePlatform};
+pub use export::{Export, ExportFormat};
+pub use html::HtmlReport;
+#[cfg(feature = "daemon")]
+pub use daemon::{Daemon, Schedule};
+#[cfg(feature = "metrics")]
+pub use metrics::Metrics;
 
 /// How many messages to pull from a channel to recent activity.
 const MESSAGE_HISTORY_LENGTH: u16 = 10;
 
+/// Longest message to post before splitting it into parts, unless the platform sets its own.
+/// Slack truncates very long messages, and recommends keeping them under 4,000 characters.
+const MAX_MESSAGE_LENGTH: usize = 4000;
+
+/// How updates are posted to the notification channel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportMode {
+  /// One message listing every channel.
+  Single,
+  /// A short summary message, with one thread reply per channel.
+  Threaded,
+}
+
+/// Which public channels the bot joins to read their history.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JoinPolicy {
+  /// Join every public channel that isn't ignored.
+  All,
+  /// Never join channels; only read those the bot is already in, or can read without joining.
+  Never,
+  /// Only join channels with these prefixes.
+  Prefixed(Vec<&'static str>),
+}
+
+/// How to handle channels shared with other organizations through Slack Connect.
+/// Archiving a shared channel archives it for the partner organization too.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SharedChannelPolicy {
+  /// Treat shared channels like any other, including archiving them. The default.
+  Include,
+  /// Leave shared channels out of updates, and never archive them.
+  Exclude,
+  /// List flagged shared channels in a section of their own, and never archive them.
+  Separate,
+  /// Report shared channels like any other, but never archive them.
+  NeverArchive,
+}
+
+/// Keys to sort channels by within each section of an update.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortKey {
+  /// Least recently active first.
+  LastActivity,
+  /// Fewest members first.
+  Members,
+  /// Alphabetically by channel name.
+  Name,
+}
+
+/// Something the bot does that changes the workspace.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Action {
+  /// Join a public channel to read its history.
+  Join {
+    /// Channel id.
+    channel_id: String,
+  },
+  /// Leave a channel that was only joined to read its history.
+  Leave {
+    /// Channel id.
+    channel_id: String,
+  },
+  /// Post a message, with any replies in a thread beneath it. Long messages are posted in parts.
+  Post {
+    /// Channel id.
+    channel_id: String,
+    /// Message text.
+    text: String,
+    /// Thread replies.
+    replies: Vec<String>,
+  },
+  /// Send a user a direct message.
+  DirectMessage {
+    /// User id.
+    user_id: String,
+    /// Message text.
+    text: String,
+  },
+  /// Upload a file to a channel.
+  Upload {
+    /// Channel id.
+    channel_id: String,
+    /// File name.
+    filename: String,
+    /// File contents.
+    content: String,
+  },
+}
+
+/// Outcome of a run.
+#[derive(Debug, Default)]
+pub struct RunReport {
+  /// Actions taken, or in a dry run, the actions that would have been taken.
+  pub actions: Vec<Action>,
+  /// Actions that were tried, but failed. Always empty in a dry run.
+  pub failed: Vec<Action>,
+  /// Analysis of every channel.
+  pub channels: Vec<ChannelData>,
+}
+
+impl RunReport {
+  /// Add an action to those taken, or those that failed.
+  fn push(&mut self, action: Action, ok: bool) {
+    match ok {
+      true => self.actions.push(action),
+      false => self.failed.push(action),
+    }
+  }
+}
+
+/// Language of messages posted by the bot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Locale {
+  /// English.
+  En,
+  /// German.
+  De,
+  /// Japanese.
+  Ja,
+}
+
+/// Templates for the wording of updates, with variables written as `{{name}}`.
+/// Templates left as `None` use the default wording.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Templates {
+  /// First line of an update.
+  /// Variables: `header` (one of `message_headers`), `count` (number of channels listed).
+  pub header: Option<&'static str>,
+  /// Each channel listed in an update.
+  /// Variables: `channel_id`, `name`, `num_members`, `last_message_date`, `days_since`, `reason`,
+  /// `section`, `team_id` (the channel's workspace on Enterprise Grid, otherwise empty), and the
+  /// default wording as `members` and `activity`.
+  pub line: Option<&'static str>,
+  /// Last line of an update. Variables: as for `header`.
+  pub footer: Option<&'static str>,
+  /// Secondary notification message.
+  /// Variables: `header` (one of `secondary_message_headers`), `notification_channel_id`.
+  pub secondary: Option<&'static str>,
+}
+
+/// How dates are shown in updates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DateFormat {
+  /// Slack date token, such as `{date_short}`, `{date_long}` or `{ago}`.
+  /// Slack shows the date in each reader's own timezone.
+  pub token: &'static str,
+  /// Format of the fallback text, for clients that can't display Slack dates; see
+  /// `chrono::format::strftime`. The locale's format if `None`.
+  pub fallback_format: Option<&'static str>,
+  /// Timezone of the fallback text.
+  pub timezone: Tz,
+  /// Whether to show how long ago, such as "43 days ago", counted from the start of the run,
+  /// rather than a date.
+  pub relative: bool,
+}
+
+impl Default for DateFormat {
+  fn default() -> DateFormat {
+    DateFormat {
+      token: "{date_short}",
+      fallback_format: None,
+      timezone: Tz::UTC,
+      relative: false,
+    }
+  }
+}
+
 /// Archive bot.
 #[derive(Debug)]
-pub struct ArchiveBot {
+pub struct ArchiveBot<P = Slack> {
   /// Slack bot token.
   pub token: String,
+  /// Chat platform to make API calls to.
+  pub platform: P,
   /// Channel id to send notifications.
   pub notification_channel_id: String,
+  /// Whether to only make read-only API calls, returning what would have been posted and joined.
+  pub dry_run: bool,
   /// Vector of channel prefixes to filter out of results.
   pub filter_prefixes: Vec<&'static str>,
+  /// Which public channels to join in order to read their history.
+  pub join_channels: JoinPolicy,
+  /// Whether to leave channels again after joining them to read their history.
+  pub leave_joined_channels: bool,
+  /// Where to export the analysis of every channel. Written on every run, including dry runs.
+  pub exports: Vec<Export>,
+  /// Where to record every join, leave, post, upload and archive, with why it was done.
+  /// Not recorded if `None`.
+  pub audit_log: Option<Box<dyn AuditSink>>,
+  /// Whether to upload a CSV export of every channel to the notification channel.
+  pub upload_csv: bool,
+  /// Where to write a standalone HTML report of every channel. Written on every run, including dry runs.
+  pub html_report: Option<HtmlReport>,
+  /// Whether to only count active, human members, excluding bots, guests and deactivated users.
+  /// Requires an extra API call per channel.
+  pub count_humans_only: bool,
+  /// How to handle channels shared with other organizations.
+  /// Channels with pending invitations that were never accepted are always listed in a section of their own.
+  pub shared_channels: SharedChannelPolicy,
+  /// Language of messages.
+  pub locale: Locale,
+  /// Language of messages posted to specific channels, overriding `locale`.
+  /// In the format: ("channel id", locale).
+  pub channel_locales: Vec<(&'static str, Locale)>,
   /// Vector of messages to send (one at random) at beginning of updates.
+  /// If empty, the locale's default messages are used.
   pub message_headers: Vec<&'static str>,
+  /// Templates for the wording of updates.
+  pub templates: Templates,
+  /// How dates are shown in updates.
+  pub dates: DateFormat,
   /// How long until a channel is stale (in seconds).
   pub stale_after: u32,
   /// The threshold <= channels are considered "small".
   pub small_channel_threshold: u16,
+  /// How old a channel must be before it can be reported as small or stale (in seconds).
+  /// Defaults to 0, reporting channels of any age.
+  pub min_channel_age: u32,
+  /// How to post updates to the notification channel.
+  pub report_mode: ReportMode,
+  /// How to sort channels within each section of an update, in order of precedence.
+  pub sort_by: Vec<SortKey>,
   /// Whether to notify a secondary channel of updates (such as #general).
   pub notify_secondary_channel: bool,
   /// Secondary channel id.
   pub secondary_notification_channel_id: String,
   /// Secondary notification message options.
+  /// If empty, the locale's default messages are used.
   pub secondary_message_headers: Vec<&'static str>,
+  /// Whether to send channel creators a direct message about their flagged channels.
+  pub notify_creators: bool,
+  /// Vector of messages to send (one at random) at beginning of direct messages to channel creators.
+  /// If empty, the locale's default messages are used.
+  pub creator_message_headers: Vec<&'static str>,
 }
 
 impl Default for ArchiveBot {
@@ -52,22 +305,33 @@ impl Default for ArchiveBot {
   fn default() -> ArchiveBot {
     ArchiveBot {
       token: "".to_string(),
+      platform: Slack,
       notification_channel_id: "".to_string(),
+      dry_run: false,
       filter_prefixes: vec![],
-      message_headers: vec![
-        "Hey, you've got some cleaning up to do!",
-        "Hey boss, take a look at these, will ya?",
-        "I don't know what this is, or what to do with it:",
-      ],
+      join_channels: JoinPolicy::All,
+      leave_joined_channels: false,
+      count_humans_only: false,
+      shared_channels: SharedChannelPolicy::Include,
+      exports: vec![],
+      audit_log: None,
+      upload_csv: false,
+      html_report: None,
+      locale: Locale::En,
+      channel_locales: vec![],
+      message_headers: vec![],
+      templates: Templates::default(),
+      dates: DateFormat::default(),
       stale_after: 6 * 7 * 24 * 60 * 60,
       small_channel_threshold: 3,
+      min_channel_age: 0,
+      report_mode: ReportMode::Single,
+      sort_by: vec![SortKey::LastActivity, SortKey::Members, SortKey::Name],
       notify_secondary_channel: false,
       secondary_notification_channel_id: "".to_string(),
-      secondary_message_headers: vec![
-        "Hey folks! I, uh... made a list for you. Of channels. That you should archive. Maybe.",
-        "Hey everyone! If you want the satisfaction of crossing a task off your list, I have one!",
-        "BEEP, BOOP! Archival update: List generated. End of program."
-      ],
+      secondary_message_headers: vec![],
+      notify_creators: false,
+      creator_message_headers: vec![],
     }
   }
 }
@@ -94,118 +358,361 @@ impl ArchiveBot {
       ..ArchiveBot::default()
     }
   }
+}
+
+impl<P: ChatPlatform> ArchiveBot<P> {
+  /// Use a different chat platform, keeping the rest of the configuration.
+  pub fn with_platform<Q: ChatPlatform>(self, platform: Q) -> ArchiveBot<Q> {
+    ArchiveBot {
+      token: self.token,
+      platform,
+      notification_channel_id: self.notification_channel_id,
+      dry_run: self.dry_run,
+      filter_prefixes: self.filter_prefixes,
+      join_channels: self.join_channels,
+      leave_joined_channels: self.leave_joined_channels,
+      exports: self.exports,
+      audit_log: self.audit_log,
+      upload_csv: self.upload_csv,
+      html_report: self.html_report,
+      count_humans_only: self.count_humans_only,
+      shared_channels: self.shared_channels,
+      locale: self.locale,
+      channel_locales: self.channel_locales,
+      message_headers: self.message_headers,
+      templates: self.templates,
+      dates: self.dates,
+      stale_after: self.stale_after,
+      small_channel_threshold: self.small_channel_threshold,
+      min_channel_age: self.min_channel_age,
+      report_mode: self.report_mode,
+      sort_by: self.sort_by,
+      notify_secondary_channel: self.notify_secondary_channel,
+      secondary_notification_channel_id: self.secondary_notification_channel_id,
+      secondary_message_headers: self.secondary_message_headers,
+      notify_creators: self.notify_creators,
+      creator_message_headers: self.creator_message_headers,
+    }
+  }
 
   /// Run Archive Bot.
-  pub async fn run(&self) -> Result<(), Box<dyn std::error::Error>> {
-    // Get channels.
-    let channels = self.get_channels().await;
+  /// Returns the actions taken, or in a dry run, the actions that would have been taken.
+  /// Fails if channels can't be listed.
+  pub async fn run(&self) -> Result<RunReport, Box<dyn std::error::Error>> {
+    let span = info_span!("run", dry_run = self.dry_run, channels = field::Empty, actions = field::Empty, failed = field::Empty, duration_ms = field::Empty);
+    let start = std::time::Instant::now();
+    let result = self.run_once().instrument(span.clone()).await;
+    span.record("duration_ms", start.elapsed().as_millis() as u64);
+    if let Ok(report) = &result {
+      span.record("channels", report.channels.len());
+      span.record("actions", report.actions.len());
+      span.record("failed", report.failed.len());
+    }
+    span.in_scope(|| match &result {
+      Ok(_) => info!("Finished run"),
+      Err(e) => info!(error = %e, "Run failed"),
+    });
+    #[cfg(feature = "metrics")]
+    Metrics::global().record_run(start.elapsed(), result.as_ref().ok().map(|report| report.channels.as_slice()));
+    result
+  }
 
-    // Parse each channel concurrently.
-    let mut tasks = vec![];
-    for channel in &channels {
-      tasks.push(self.parse_channel(&channel));
+  /// Scan, post and notify for a single run.
+  async fn run_once(&self) -> Result<RunReport, Box<dyn std::error::Error>> {
+    // Ages in messages are counted from the start of the run.
+    let now = chrono::offset::Utc::now().timestamp();
+    let channels_data = self.try_scan().await?;
+    self.export(&channels_data);
+    self.write_html_report(&channels_data);
+
+    let mut report = RunReport::default();
+    for channel in channels_data.iter().filter(|c| c.joined || c.join_failed) {
+      report.push(Action::Join { channel_id: channel.id.clone() }, channel.joined);
+      if channel.joined && self.leave_joined_channels {
+        report.push(Action::Leave { channel_id: channel.id.clone() }, channel.left);
+      }
     }
-    let channels_data = future::join_all(tasks).await;
+
+    // The bot's name is only needed to suggest inviting it to private channels it can't read.
+    let bot_name = match channels_data.iter().any(|c| self.channel_section(c) == Some(Section::Private)) {
+      true => self.get_bot_name().await,
+      false => None,
+    };
 
     // Build and send message.
-    let message = self.create_message(&channels_data);
-    if message != "" {
-      if let Ok(_) = self.post_message(&self.notification_channel_id, &message).await {
-        info!("Posted update in {:}", self.notification_channel_id);
+    if let Some(update) = self.create_update(&channels_data, bot_name.as_deref(), now) {
+      let posted = self.dry_run || self.perform(&update, &self.update_reason(&channels_data)).await;
+      report.push(update, posted);
+      if posted {
+        info!(channel_id = %self.notification_channel_id, "Posted update");
         if self.notify_secondary_channel {
-          let secondary_message = self.create_secondary_message();
-          if let Ok(_) = self.post_message(&self.secondary_notification_channel_id, &secondary_message).await {
-            info!("Posted secondary update in {:}", self.secondary_notification_channel_id);
+          let secondary = Action::Post {
+            channel_id: self.secondary_notification_channel_id.clone(),
+            text: self.create_secondary_message(self.catalog(&self.secondary_notification_channel_id)),
+            replies: vec![],
+          };
+          let posted = self.dry_run || self.perform(&secondary, "notify_secondary_channel").await;
+          if posted {
+            info!(channel_id = %self.secondary_notification_channel_id, "Posted secondary update");
           }
+          report.push(secondary, posted);
         }
       }
     }
 
-    Ok(())
+    if self.upload_csv {
+      let mut content = vec![];
+      ExportFormat::Csv.write(&channels_data, &mut content)?;
+      let upload = Action::Upload {
+        channel_id: self.notification_channel_id.clone(),
+        filename: format!("channels-{}.csv", chrono::offset::Utc::now().format("%Y-%m-%d")),
+        content: String::from_utf8(content)?,
+      };
+      let uploaded = self.dry_run || self.perform(&upload, "upload_csv").await;
+      if uploaded && !self.dry_run {
+        info!(channel_id = %self.notification_channel_id, "Uploaded channel export");
+      }
+      report.push(upload, uploaded);
+    }
+
+    if self.notify_creators {
+      for dm in self.create_creator_messages(&channels_data, now).await {
+        let sent = self.dry_run || self.perform(&dm, "notify_creators").await;
+        report.push(dm, sent);
+      }
+    }
+
+    report.channels = channels_data;
+    Ok(report)
   }
 
-  /// Parse data to create regular update message to post regarding channel status.
-  fn create_message(&self, data: &Vec<ChannelData>) -> String {
-    let mut message: String = "".to_string();
-    for channel in data {
-      if self.channel_should_be_mentioned(&channel) {
-        let s: &str = match channel.num_members {
-          1 => "",
-          _ => "s",
-        };
-        let mbr_msg: String = match channel {
-          ChannelData { is_small: true, .. } => format!("has *{} member{}*.", channel.num_members, s),
-          _ => format!("has {} member{}.", channel.num_members, s),
-        };
+  /// Get and parse every channel, joining channels as configured.
+  pub async fn scan(&self) -> Vec<ChannelData> {
+    self.try_scan().await.unwrap_or_default()
+  }
 
-        let time_msg: String = match channel {
-          ChannelData { is_private: true, .. } => "The channel is private, so I can't read the latest message.".into(),
-          ChannelData { last_message_ts: 0, .. } => "No recent messages.".into(),
-          ChannelData { last_message_relevant: false, is_old: true, .. } => format!("The last event was on *{date}*, but there are no recent messages.", date=channel.last_message_ts_formatted()),
-          ChannelData { last_message_relevant: false, is_old: false, .. } => format!("The last event was on {date}, but there are no recent messages.", date=channel.last_message_ts_formatted()),
-          ChannelData { is_old: true, .. } => format!("The last message was on *{date}*.", date=channel.last_message_ts_formatted()),
-          _ => format!("The last message was on {date}.", date=channel.last_message_ts_formatted()),
-        };
+  /// Get and parse every channel, joining channels as configured. Fails if channels can't be listed.
+  async fn try_scan(&self) -> Result<Vec<ChannelData>, P::Error> {
+    let channels = self.try_get_channels().await?;
+    let humans = self.get_humans().await;
+
+    // Parse each channel concurrently.
+    let mut tasks = vec![];
+    for channel in &channels {
+      tasks.push(self.parse_channel(channel, humans.as_ref()));
+    }
+    Ok(future::join_all(tasks).await)
+  }
+
+  /// Get and parse a single channel, by id or name.
+  pub async fn check_channel(&self, channel: &str) -> Option<ChannelData> {
+    let name = channel.trim_start_matches('#');
+    let channels = self.get_channels().await;
+    let channel = channels.iter().find(|c| c.id == channel || c.name == name)?;
+    let humans = self.get_humans().await;
+    Some(self.parse_channel(channel, humans.as_ref()).await)
+  }
+
+  /// Whether a channel may be archived under the shared channel policy.
+  /// Channels shared with, or pending an invitation to, another organization are only archived if the
+  /// policy is `Include`. Channels shared between workspaces of the same organization aren't covered.
+  pub fn may_archive(&self, channel: &Channel) -> bool {
+    !(channel.is_ext_shared || channel.is_pending_ext_shared) || self.shared_channels == SharedChannelPolicy::Include
+  }
 
-        // mrkdwn parsed, but no list format; using * breaks *bold* text
-        message.push_str(&format!(
-          "- <#{id}> {members} {time}\n",
-          id=channel.id,
-          members=mbr_msg,
-          time=time_msg
-        ));
+  /// Whether a channel would be included in an update.
+  pub fn channel_is_flagged(&self, channel: &ChannelData) -> bool {
+    self.channel_section(channel).is_some()
+  }
+
+  /// Check the configuration for problems that would prevent the bot from running properly.
+  /// Doesn't make any API calls.
+  pub fn validate(&self) -> Vec<String> {
+    let mut errors = vec![];
+    if self.token.is_empty() {
+      errors.push("token is not set".to_string());
+    } else if let Err(e) = self.platform.validate_token(&self.token) {
+      errors.push(e);
+    }
+    if self.notification_channel_id.is_empty() {
+      errors.push("notification_channel_id is not set".to_string());
+    }
+    if self.notify_secondary_channel && self.secondary_notification_channel_id.is_empty() {
+      errors.push("notify_secondary_channel is set, but secondary_notification_channel_id is not".to_string());
+    }
+    if self.sort_by.is_empty() {
+      errors.push("sort_by is empty".to_string());
+    }
+    if let Some(format) = self.dates.fallback_format {
+      if StrftimeItems::new(format).any(|item| item == Item::Error) {
+        errors.push(format!("dates.fallback_format \"{}\" is not a valid date format", format));
       }
     }
+    errors
+  }
 
-    if message != "" {
-      let prefix = self.message_headers.choose(&mut rand::thread_rng()).unwrap().to_string();
-      message = format!("{}\n{}", prefix, message);
+  /// Users are listed once per run, rather than looked up per channel.
+  async fn get_humans(&self) -> Option<HashSet<String>> {
+    match self.count_humans_only {
+      true => self.get_human_user_ids().await,
+      false => None,
     }
+  }
 
-    message
+  /// Create the update for the notification channel, if there's anything to report.
+  fn create_update(&self, data: &[ChannelData], bot_name: Option<&str>, now: i64) -> Option<Action> {
+    let catalog = self.catalog(&self.notification_channel_id);
+    let (text, replies) = match self.report_mode {
+      ReportMode::Single => (self.create_message(catalog, data, bot_name, now), vec![]),
+      ReportMode::Threaded => {
+        let replies = self.create_thread_replies(catalog, data, bot_name, now);
+        match replies.is_empty() {
+          true => ("".to_string(), replies),
+          false => (self.create_summary_message(catalog, data), replies),
+        }
+      },
+    };
+    match text.is_empty() {
+      true => None,
+      false => Some(Action::Post { channel_id: self.notification_channel_id.clone(), text, replies }),
+    }
   }
 
-  /// Whether a channel should be included in updates.
-  fn channel_should_be_mentioned(&self, channel: &ChannelData) -> bool {
-    (channel.is_old || channel.is_small) && !channel.is_ignored
+  /// Create one direct message per channel creator listing all of their flagged channels.
+  async fn create_creator_messages(&self, data: &[ChannelData], now: i64) -> Vec<Action> {
+    let mut by_creator: BTreeMap<&str, Vec<&ChannelData>> = BTreeMap::new();
+    for channel in data.iter().filter(|c| self.channel_should_be_mentioned(c)) {
+      if let Some(creator) = &channel.creator {
+        by_creator.entry(creator).or_default().push(channel);
+      }
+    }
+
+    let mut messages = vec![];
+    for (creator, channels) in by_creator {
+      match self.get_user(creator).await {
+        Some(User { deleted: false, .. }) => {},
+        Some(_) => {
+          debug!(user_id = %creator, "Not notifying deactivated user");
+          continue;
+        },
+        None => continue,
+      }
+      messages.push(Action::DirectMessage {
+        user_id: creator.to_string(),
+        text: self.create_creator_message(self.locale.catalog(), &channels, now),
+      });
+    }
+    messages
   }
 
-  /// Create secondary notification message.
-  fn create_secondary_message(&self) -> String {
-    let line_a = self.secondary_message_headers.choose(&mut rand::thread_rng()).unwrap().to_string();
-    format!("{} See <#{}> for details.", line_a, self.notification_channel_id)
+  /// Perform an action, recording why in the audit log. Returns whether it succeeded.
+  /// A threaded post only succeeds if every reply is posted too.
+  async fn perform(&self, action: &Action, reason: &str) -> bool {
+    match action {
+      Action::Join { channel_id } => self.join_channel_because(channel_id, reason).await.is_ok(),
+      Action::Leave { channel_id } => self.leave_channel_because(channel_id, reason).await.is_ok(),
+      Action::Post { channel_id, text, replies } if replies.is_empty() => {
+        self.post_parts_because(channel_id, None, text, reason).await.is_ok()
+      },
+      Action::Post { channel_id, text, replies } => {
+        let thread_id = match self.post(channel_id, text, None, reason).await {
+          Ok(id) => id,
+          Err(_) => return false,
+        };
+        let mut posted = true;
+        for reply in replies {
+          // Failures are logged; keep going so one bad reply doesn't hide the rest.
+          posted &= self.post_parts_because(channel_id, Some(&thread_id), reply, reason).await.is_ok();
+        }
+        posted
+      },
+      Action::DirectMessage { user_id, text } => {
+        let posted = match self.open_direct_message(user_id).await {
+          Ok(dm_id) => self.post_parts_because(&dm_id, None, text, reason).await.is_ok(),
+          Err(_) => false,
+        };
+        if posted {
+          info!(user_id = %user_id, "Notified creator of their channels");
+        }
+        posted
+      },
+      Action::Upload { channel_id, filename, content } => {
+        self.upload_file_because(channel_id, filename, content.as_bytes(), reason).await.is_ok()
+      },
+    }
   }
 
   /// Parse a specific channel for relevant data, fetching missing data where necessary.
-  async fn parse_channel(&self, channel: &Channel) -> ChannelData {
+  /// If `humans` is given, only those users are counted as members.
+  #[tracing::instrument(name = "channel", skip_all, fields(channel_id = %channel.id, name = %channel.name))]
+  async fn parse_channel(&self, channel: &Channel, humans: Option<&HashSet<String>>) -> ChannelData {
     let is_ignored = self.channel_is_ignored(&channel.name);
 
-    let is_member = match is_ignored {
-      true => channel.is_member,
-      false => self.maybe_join_channel(&channel).await,
+    // In a dry run, note channels that would be joined rather than joining them.
+    let needs_join = !P::READS_WITHOUT_JOINING && !is_ignored && !channel.is_member && !channel.is_private && self.should_join(&channel.name);
+    let joined = needs_join && match self.dry_run {
+      true => true,
+      false => self.maybe_join_channel(channel).await,
     };
+    let join_failed = needs_join && !joined;
+    let is_member = channel.is_member || (joined && !self.dry_run);
+
+    let now = chrono::offset::Utc::now().timestamp();
+    let created: i64 = channel.created.map(i64::from).unwrap_or(0);
+    let is_new = created > now - self.min_channel_age as i64;
 
     let mut last_message_ts = 0;
     let mut last_message_relevant = false;
     let mut is_old = false;
 
-    if is_member && !is_ignored {
-      if let Some(msg) = self.get_last_message(&channel).await {
+    // Unless the join policy skipped it, the bot should be able to read the channel.
+    let expect_member = is_member || (!self.dry_run && !P::READS_WITHOUT_JOINING && self.should_join(&channel.name));
+
+    // Try public channels even if not a member; some tokens can read them without joining, and in a
+    // dry run, that's the closest to what a real run would read.
+    let history = match !is_ignored && (is_member || !channel.is_private || P::READS_WITHOUT_JOINING) {
+      true => self.get_history(&channel.id, MESSAGE_HISTORY_LENGTH, expect_member).await,
+      false => Ok(None),
+    };
+    let history_failed = history.is_err();
+    let history = history.ok().flatten();
+    let is_readable = history.is_some();
+
+    let left = joined && self.leave_joined_channels && match self.dry_run {
+      true => true,
+      false => self.leave_channel_because(&channel.id, "leave_joined_channels").await.is_ok(),
+    };
+    if left && !self.dry_run {
+      info!("Left channel");
+    }
+
+    if let Some(history) = history {
+      if let Some(msg) = last_message(&history) {
         if let Some(ts) = msg.ts {
           last_message_ts = ts.into();
         }
         last_message_relevant = !msg.ignore_type();
-        let now = chrono::offset::Utc::now().timestamp();
         is_old = last_message_ts > 0 && last_message_ts < now - self.stale_after as i64;
       };
+      // Without any history, judge the channel by when it was created.
+      if last_message_ts == 0 {
+        is_old = created > 0 && created < now - self.stale_after as i64;
+      }
     }
 
-    // Don't count self as a member.
-    let num_members = match is_member {
-      true => channel.num_members - 1,
-      false => channel.num_members,
+    let human_members = match (humans, is_ignored) {
+      (Some(humans), false) => self.get_members(&channel.id).await
+        .map(|members| members.iter().filter(|id| humans.contains(*id)).count() as i32),
+      _ => None,
+    };
+    // Don't count self as a member. Member counts are from before joining.
+    let num_members = match (human_members, channel.is_member) {
+      (Some(count), _) => count,
+      (None, true) => channel.num_members - 1,
+      (None, false) => channel.num_members,
     };
     let is_small = num_members <= self.small_channel_threshold as i32;
+    debug!(num_members, last_message_ts, is_old, is_small, is_new, is_ignored, is_readable, joined, "Parsed channel");
 
     ChannelData {
       id: channel.id.clone(),
@@ -213,40 +720,59 @@ impl ArchiveBot {
       last_message_ts,
       last_message_relevant,
       num_members,
+      created,
       is_old,
       is_small,
+      is_new,
       is_ignored,
       is_private: channel.is_private,
+      is_ext_shared: channel.is_ext_shared,
+      is_pending_ext_shared: channel.is_pending_ext_shared,
+      is_member,
+      is_readable,
+      history_failed,
+      joined,
+      join_failed,
+      left,
+      creator: channel.creator.clone(),
+      team_id: channel.team_id.clone(),
+    }
+  }
+
+  /// Whether to join a public channel the bot isn't a member of, based on config.
+  fn should_join(&self, channel_name: &str) -> bool {
+    match &self.join_channels {
+      JoinPolicy::All => true,
+      JoinPolicy::Never => false,
+      JoinPolicy::Prefixed(prefixes) => prefixes.iter().any(|p| channel_name.starts_with(p)),
     }
   }
 
-  /// Join a channel (maybe). Returns whether the bot is now a member of the channel.
+  /// Join a channel (maybe). Returns whether the bot joined the channel.
   async fn maybe_join_channel(&self, channel: &Channel) -> bool {
-    if !channel.is_member && !channel.is_private {
-      log::debug!("Need to join channel #{:} ({:})", channel.name, channel.id);
-      if let Ok(_) = self.join_channel(&channel.id).await {
-        info!("Joined channel #{:} ({:})", channel.name, channel.id);
-        return true;
-      }
+    debug!("Need to join channel");
+    if self.join_channel_because(&channel.id, &self.join_reason(&channel.name)).await.is_ok() {
+      info!("Joined channel");
+      return true;
     }
-    channel.is_member
+    false
   }
 
-  /// Get timestamp of last message in a channel.
-  async fn get_last_message(&self, channel: &Channel) -> Option<Message> {
-    if let Some(history) = self.get_history(&channel.id, MESSAGE_HISTORY_LENGTH).await {
-      for message in history.clone() {
-        if !message.ignore_type() {
-          if let Some(_ts) = message.ts {
-            return Some(message);
-          }
-        }
-      }
-      if let Some(first) = history.first() {
-        return Some(first.to_owned());
-      }
+  /// Why a channel is joined, for the audit log.
+  fn join_reason(&self, channel_name: &str) -> String {
+    match &self.join_channels {
+      JoinPolicy::Prefixed(prefixes) => {
+        let prefix = prefixes.iter().find(|p| channel_name.starts_with(*p)).unwrap_or(&"");
+        format!("join_channels: prefix {}", prefix)
+      },
+      JoinPolicy::All | JoinPolicy::Never => "join_channels: all".to_string(),
     }
-    None
+  }
+
+  /// Why an update is posted, for the audit log. Always in English, whatever the locale.
+  fn update_reason(&self, data: &[ChannelData]) -> String {
+    let (count, sections) = self.section_counts(Locale::En.catalog(), data);
+    format!("update: {} flagged {}: {}", count, if count == 1 { "channel" } else { "channels" }, sections)
   }
 
   /// Whether the channel is ignored based on config.
@@ -255,26 +781,38 @@ impl ArchiveBot {
   }
 }
 
+/// Get the last relevant message in a channel's history, or else the latest event.
+fn last_message(history: &[Message]) -> Option<Message> {
+  history.iter()
+    .find(|message| !message.ignore_type() && message.ts.is_some())
+    .or_else(|| history.first())
+    .cloned()
+}
+
 #[cfg(test)]
 mod tests {
   #[cfg(any(feature = "unit", feature="unit_output"))]
   use super::*;
-  #[cfg(feature="unit_output")]
-  use simplelog;
+  #[cfg(feature = "unit")]
+  use std::collections::HashMap;
 
   /// Create a test message and print it to stdout rather than posting to Slack.
   #[tokio::test]
   #[cfg(feature = "unit_output")]
   async fn test_create_message() {
     simplelog::TermLogger::init(simplelog::LevelFilter::Debug, simplelog::Config::default(), simplelog::TerminalMode::Mixed, simplelog::ColorChoice::Auto).unwrap();
-    let bot = crate::ArchiveBot::_from_env_debug();
+    let bot = ArchiveBot {
+      dry_run: true,
+      ..ArchiveBot::_from_env_debug()
+    };
 
-    let mut channels_data: Vec<ChannelData> = vec![];
-    for channel in bot.get_channels().await {
-      channels_data.push(bot.parse_channel(&channel).await);
+    let report = bot.run().await.unwrap();
+    for action in report.actions {
+      match action {
+        Action::Post { text, replies, .. } => println!("Message:\n{:}\n{:}", text, replies.join("\n")),
+        _ => println!("{:?}", action),
+      }
     }
-    let message = bot.create_message(&channels_data);
-    println!("Message:\n{:}", message);
   }
 
   /// Create a test secondary message and print it to stdout rather than posting to Slack.
@@ -282,8 +820,8 @@ mod tests {
   #[cfg(feature = "unit_output")]
   fn test_create_secondary_message() {
     simplelog::TermLogger::init(simplelog::LevelFilter::Debug, simplelog::Config::default(), simplelog::TerminalMode::Mixed, simplelog::ColorChoice::Auto).unwrap();
-    let bot = crate::ArchiveBot::_from_env_debug();
-    let message = bot.create_secondary_message();
+    let bot = ArchiveBot::_from_env_debug();
+    let message = bot.create_secondary_message(bot.locale.catalog());
     println!("Message:\n{:}", message);
   }
 
@@ -291,9 +829,9 @@ mod tests {
   #[tokio::test]
   #[cfg(feature = "unit")]
   async fn test_filter_channels() {
-    let bot = crate::ArchiveBot::_from_env_debug();
+    let bot = ArchiveBot::_from_env_debug();
     // (channel name, should be ignored)
-    let channels = vec![
+    let channels = [
       ("testing", false),
       ("-prefixed", true),
       ("ext-another", true),
@@ -307,50 +845,469 @@ mod tests {
   #[tokio::test]
   #[cfg(feature = "unit")]
   async fn test_parse_channel() {
-    use crate::types::ChannelData;
-
-    let bot = crate::ArchiveBot::_from_env_debug();
+    let bot = ArchiveBot::default().with_platform(FakePlatform::default());
 
-    let channel: Channel = Channel {
+    let channel = Channel {
       id: "fake_id".to_string(),
       name: "fake-name".to_string(),
       is_channel: true,
-      is_group: false,
-      is_im: false,
-      is_archived: false,
-      is_general: false,
-      unlinked: false,
-      is_read_only: false,
-      is_shared: false,
-      is_ext_shared: false,
-      is_org_shared: false,
-      is_pending_ext_shared: false,
-      is_member: false,
       is_private: true,
-      is_mpim: false,
       num_members: 3,
-      creator: None,
-      created: None,
-      last_read: None,
-      name_normalized: None,
-      pending_shared: None,
-      previous_names: None,
+      ..Channel::default()
     };
 
     let test_channel_data: ChannelData = ChannelData {
       id: "fake_id".to_string(),
       name: "fake-name".to_string(),
-      last_message_ts: 0,
-      last_message_relevant: false,
       num_members: 3,
-      is_old: false,
       is_small: true,
-      is_ignored: false,
       is_private: true,
+      ..ChannelData::default()
     };
 
-    let data = bot.parse_channel(&channel).await;
+    let data = bot.parse_channel(&channel, None).await;
     assert_eq!(data, test_channel_data);
   }
 
+  /// Test that private channels are only scanned if the bot is a member.
+  #[tokio::test]
+  #[cfg(feature = "unit")]
+  async fn test_private_channels() {
+    let now = chrono::offset::Utc::now().timestamp();
+    let channel = |id: &str, is_member: bool| Channel {
+      id: id.to_string(),
+      name: id.to_lowercase(),
+      is_private: true,
+      is_member,
+      num_members: 10,
+      created: Some(Timestamp::from(now - 365 * 24 * 60 * 60)),
+      ..Channel::default()
+    };
+    let stale = vec![Message {
+      event_type: "message".to_string(),
+      ts: Some(Timestamp::from(now - 100 * 24 * 60 * 60)),
+      ..Message::default()
+    }];
+    let mut platform = FakePlatform::default();
+    platform.channels = vec![channel("C01", true), channel("C02", false)];
+    platform.histories = HashMap::from([("C01".to_string(), stale.clone()), ("C02".to_string(), stale)]);
+    let bot = ArchiveBot::default().with_platform(platform);
+
+    let data = bot.scan().await;
+    assert!(data[0].is_readable && !data[0].is_unreadable());
+    assert!(data[0].is_old);
+    assert_eq!(bot.channel_section(&data[0]), Some(Section::Stale));
+    assert!(!data[1].is_readable && data[1].is_unreadable());
+    assert!(!data[1].is_old);
+    assert_eq!(bot.channel_section(&data[1]), Some(Section::Private));
+    assert!(bot.platform.calls().is_empty());
+  }
+
+  /// Test that channels younger than `min_channel_age` aren't reported, and that channels without
+  /// history are judged by when they were created.
+  #[tokio::test]
+  #[cfg(feature = "unit")]
+  async fn test_channel_age() {
+    let now = chrono::offset::Utc::now().timestamp();
+    let days = |n: i64| now - n * 24 * 60 * 60;
+    let channel = |id: &str, created: i64| Channel {
+      id: id.to_string(),
+      name: id.to_lowercase(),
+      is_channel: true,
+      is_member: true,
+      num_members: 10,
+      created: Some(Timestamp::from(created)),
+      ..Channel::default()
+    };
+    let bot = |min_channel_age: u32| {
+      let mut platform = FakePlatform::default();
+      platform.channels = vec![channel("C01", days(2)), channel("C02", days(20)), channel("C03", days(365))];
+      platform.histories = HashMap::from([
+        ("C01".to_string(), vec![]),
+        ("C02".to_string(), vec![]),
+        ("C03".to_string(), vec![]),
+      ]);
+      ArchiveBot { min_channel_age, small_channel_threshold: 10, ..ArchiveBot::default() }.with_platform(platform)
+    };
+    let sections = |bot: &ArchiveBot<FakePlatform>, data: &[ChannelData]| data.iter()
+      .map(|c| bot.channel_section(c))
+      .collect::<Vec<_>>();
+
+    let all = bot(0);
+    let data = all.scan().await;
+    assert!(data.iter().all(|c| !c.is_new && c.is_readable && c.last_message_ts == 0));
+    assert_eq!(data.iter().map(|c| c.is_old).collect::<Vec<_>>(), vec![false, false, true]);
+    assert_eq!(sections(&all, &data), vec![Some(Section::Small), Some(Section::Small), Some(Section::StaleAndSmall)]);
+
+    let week = bot(7 * 24 * 60 * 60);
+    let data = week.scan().await;
+    assert_eq!(data.iter().map(|c| c.is_new).collect::<Vec<_>>(), vec![true, false, false]);
+    assert_eq!(sections(&week, &data), vec![None, Some(Section::Small), Some(Section::StaleAndSmall)]);
+  }
+
+  /// Test which public channels each join policy joins, and leaving them again after scanning.
+  #[tokio::test]
+  #[cfg(feature = "unit")]
+  async fn test_join_policies() {
+    let channel = |id: &str, name: &str, is_member: bool| Channel {
+      id: id.to_string(),
+      name: name.to_string(),
+      is_channel: true,
+      is_member,
+      num_members: 10,
+      ..Channel::default()
+    };
+    let bot = |join_channels: JoinPolicy, leave_joined_channels: bool| {
+      let mut platform = FakePlatform::default();
+      platform.channels = vec![
+        channel("C01", "project-a", false),
+        channel("C02", "random", false),
+        channel("C03", "general", true),
+      ];
+      platform.histories = HashMap::from([
+        ("C01".to_string(), vec![]),
+        ("C02".to_string(), vec![]),
+        ("C03".to_string(), vec![]),
+      ]);
+      platform.history_needs_membership = true;
+      ArchiveBot { join_channels, leave_joined_channels, ..ArchiveBot::default() }.with_platform(platform)
+    };
+    let calls = |bot: &ArchiveBot<FakePlatform>| {
+      let mut calls = bot.platform.calls();
+      calls.sort_by_key(|c| format!("{:?}", c));
+      calls
+    };
+    let join = |id: &str| FakeCall::Join(id.to_string());
+    let leave = |id: &str| FakeCall::Leave(id.to_string());
+
+    let all = bot(JoinPolicy::All, false);
+    let data = all.scan().await;
+    assert_eq!(calls(&all), vec![join("C01"), join("C02")]);
+    assert!(data.iter().all(|c| c.is_readable && !c.left));
+    assert_eq!(data.iter().map(|c| c.joined).collect::<Vec<_>>(), vec![true, true, false]);
+
+    let never = bot(JoinPolicy::Never, false);
+    let data = never.scan().await;
+    assert!(calls(&never).is_empty());
+    assert_eq!(data.iter().map(|c| c.is_readable).collect::<Vec<_>>(), vec![false, false, true]);
+    assert!(data.iter().all(|c| !c.joined));
+
+    let prefixed = bot(JoinPolicy::Prefixed(vec!["project-"]), false);
+    let data = prefixed.scan().await;
+    assert_eq!(calls(&prefixed), vec![join("C01")]);
+    assert_eq!(data.iter().map(|c| c.joined).collect::<Vec<_>>(), vec![true, false, false]);
+
+    let leaving = bot(JoinPolicy::All, true);
+    let data = leaving.scan().await;
+    assert_eq!(calls(&leaving), vec![join("C01"), join("C02"), leave("C01"), leave("C02")]);
+    assert_eq!(data.iter().map(|c| c.left).collect::<Vec<_>>(), vec![true, true, false]);
+    assert!(data.iter().all(|c| c.is_readable));
+  }
+
+  /// Test that bots, deactivated users and guests don't count toward a channel's members.
+  #[tokio::test]
+  #[cfg(feature = "unit")]
+  async fn test_count_humans_only() {
+    let user = |id: &str| User { id: id.to_string(), ..User::default() };
+    let bot = |count_humans_only: bool| {
+      let mut platform = FakePlatform::default();
+      platform.channels = vec![Channel {
+        id: "C01".to_string(),
+        name: "bots".to_string(),
+        is_channel: true,
+        num_members: 6,
+        ..Channel::default()
+      }];
+      platform.users = vec![
+        user("U01"),
+        user("U02"),
+        User { is_bot: true, ..user("B01") },
+        User { deleted: true, ..user("U03") },
+        User { is_guest: true, ..user("G01") },
+        user("USLACKBOT"),
+      ];
+      platform.members = HashMap::from([("C01".to_string(), platform.users.iter().map(|u| u.id.clone()).collect())]);
+      ArchiveBot { count_humans_only, small_channel_threshold: 2, ..ArchiveBot::default() }.with_platform(platform)
+    };
+
+    let data = bot(false).scan().await;
+    assert_eq!(data[0].num_members, 6);
+    assert!(!data[0].is_small);
+
+    let data = bot(true).scan().await;
+    assert_eq!(data[0].num_members, 2);
+    assert!(data[0].is_small);
+
+    let guest: User = serde_json::from_str(r#"{"id":"G01","is_restricted":true,"is_ultra_restricted":true}"#).unwrap();
+    assert!(guest.is_guest && !guest.is_active_human());
+  }
+
+  /// Test that a channel whose history couldn't be read isn't mistaken for one the bot hasn't joined.
+  #[tokio::test]
+  #[cfg(feature = "unit")]
+  async fn test_history_failed() {
+    let mut platform = FakePlatform::default();
+    platform.channels = vec![Channel {
+      id: "C01".to_string(),
+      name: "secret".to_string(),
+      is_private: true,
+      is_member: true,
+      num_members: 2,
+      ..Channel::default()
+    }];
+    platform.failing = vec!["history"];
+    let bot = ArchiveBot::default().with_platform(platform);
+
+    let data = bot.scan().await;
+    assert!(data[0].history_failed && !data[0].is_readable);
+    assert!(!data[0].is_unreadable() && !data[0].is_history_unknown());
+    let catalog = bot.locale.catalog();
+    let message = bot.create_message(catalog, &data, None, 0);
+    assert!(message.contains(catalog.history_failed));
+    assert!(!message.contains(catalog.history_unknown));
+  }
+
+  /// Test a full run against the fake platform.
+  #[tokio::test]
+  #[cfg(feature = "unit")]
+  async fn test_run() {
+    let now = chrono::offset::Utc::now().timestamp();
+    let channel = |id: &str, name: &str, is_member: bool| Channel {
+      id: id.to_string(),
+      name: name.to_string(),
+      is_channel: true,
+      is_member,
+      num_members: 10,
+      created: Some(Timestamp::from(now - 365 * 24 * 60 * 60)),
+      ..Channel::default()
+    };
+    let message = |ts: i64| Message {
+      event_type: "message".to_string(),
+      ts: Some(Timestamp::from(ts)),
+      ..Message::default()
+    };
+
+    let fake = |failing: Vec<&'static str>| {
+      let mut platform = FakePlatform::default();
+      platform.bot_name = "archive-bot".to_string();
+      platform.channels = vec![
+        channel("C01", "active", true),
+        channel("C02", "stale", false),
+        channel("C03", "-ignored", false),
+      ];
+      platform.histories = HashMap::from([
+        ("C01".to_string(), vec![message(now - 60)]),
+        ("C02".to_string(), vec![message(now - 100 * 24 * 60 * 60)]),
+      ]);
+      platform.failing = failing;
+      ArchiveBot {
+        notification_channel_id: "C00".to_string(),
+        filter_prefixes: vec!["-"],
+        leave_joined_channels: true,
+        ..ArchiveBot::default()
+      }.with_platform(platform)
+    };
+
+    let bot = fake(vec!["leave"]);
+    let report = bot.run().await.unwrap();
+    assert_eq!(report.actions.len(), 2);
+    assert_eq!(report.failed, vec![Action::Leave { channel_id: "C02".to_string() }]);
+
+    let calls = bot.platform.calls();
+    assert_eq!(calls[0], FakeCall::Join("C02".to_string()));
+    match &calls[1..] {
+      [FakeCall::Post { channel_id, thread_id: None, text }] => {
+        assert_eq!(channel_id, "C00");
+        assert!(text.contains("<#C02>"));
+        assert!(!text.contains("<#C01>"));
+        assert!(!text.contains("<#C03>"));
+      },
+      calls => panic!("unexpected calls: {:?}", calls),
+    }
+
+    // A failed join is reported, and there's nothing to leave.
+    let bot = fake(vec!["join"]);
+    let report = bot.run().await.unwrap();
+    assert_eq!(report.failed, vec![Action::Join { channel_id: "C02".to_string() }]);
+    assert!(report.channels[1].join_failed && !report.channels[1].joined);
+    assert!(!bot.platform.calls().iter().any(|c| matches!(c, FakeCall::Join(_) | FakeCall::Leave(_))));
+  }
+
+  /// Test that a threaded update whose replies fail is reported as failed.
+  #[tokio::test]
+  #[cfg(feature = "unit")]
+  async fn test_failed_reply() {
+    let now = chrono::offset::Utc::now().timestamp();
+    let mut platform = FakePlatform::default();
+    platform.channels = ["C01", "C02"].iter().map(|id| Channel {
+      id: id.to_string(),
+      name: id.to_lowercase(),
+      is_channel: true,
+      is_member: true,
+      num_members: 10,
+      ..Channel::default()
+    }).collect();
+    platform.histories = platform.channels.iter().map(|c| (c.id.clone(), vec![Message {
+      event_type: "message".to_string(),
+      ts: Some(Timestamp::from(now - 100 * 24 * 60 * 60)),
+      ..Message::default()
+    }])).collect();
+    platform.failing = vec!["reply"];
+    let bot = ArchiveBot {
+      notification_channel_id: "C00".to_string(),
+      report_mode: ReportMode::Threaded,
+      ..ArchiveBot::default()
+    }.with_platform(platform);
+
+    let report = bot.run().await.unwrap();
+    assert!(matches!(report.failed.as_slice(), [Action::Post { channel_id, replies, .. }] if channel_id == "C00" && !replies.is_empty()));
+    match bot.platform.calls().as_slice() {
+      [FakeCall::Post { channel_id, thread_id: None, .. }] => assert_eq!(channel_id, "C00"),
+      calls => panic!("unexpected calls: {:?}", calls),
+    }
+  }
+
+  /// Test that a dry run reports a public channel the bot isn't in as a real run would.
+  #[tokio::test]
+  #[cfg(feature = "unit")]
+  async fn test_dry_run() {
+    let now = chrono::offset::Utc::now().timestamp();
+    let bot = |dry_run: bool, join_channels: JoinPolicy, history_needs_membership: bool| {
+      let mut platform = FakePlatform::default();
+      platform.channels = vec![Channel {
+        id: "C01".to_string(),
+        name: "stale".to_string(),
+        is_channel: true,
+        num_members: 10,
+        created: Some(Timestamp::from(now - 365 * 24 * 60 * 60)),
+        ..Channel::default()
+      }];
+      platform.histories = HashMap::from([("C01".to_string(), vec![Message {
+        event_type: "message".to_string(),
+        ts: Some(Timestamp::from(now - 100 * 24 * 60 * 60)),
+        ..Message::default()
+      }])]);
+      platform.history_needs_membership = history_needs_membership;
+      ArchiveBot { dry_run, join_channels, ..ArchiveBot::default() }.with_platform(platform)
+    };
+    let sections = |bot: &ArchiveBot<FakePlatform>, data: &[ChannelData]| bot.sections(data).into_iter()
+      .map(|(section, channels)| (section, channels.iter().map(|c| c.name.clone()).collect::<Vec<_>>()))
+      .collect::<Vec<_>>();
+
+    let real = bot(false, JoinPolicy::All, false);
+    let real_sections = sections(&real, &real.scan().await);
+    assert_eq!(real_sections, vec![(Section::Stale, vec!["stale".to_string()])]);
+    let dry = bot(true, JoinPolicy::All, false);
+    let data = dry.scan().await;
+    assert_eq!(sections(&dry, &data), real_sections);
+    assert!(data[0].joined && dry.platform.calls().is_empty());
+
+    // Without joining, history can't be read, but the channel isn't mistaken for a private one.
+    for bot in [bot(true, JoinPolicy::All, true), bot(false, JoinPolicy::Never, true)] {
+      let data = bot.scan().await;
+      assert!(data[0].is_history_unknown() && !data[0].is_unreadable());
+      assert_eq!(bot.channel_section(&data[0]), None);
+    }
+  }
+
+  /// Test grouping channels into sorted sections.
+  #[test]
+  #[cfg(feature = "unit")]
+  fn test_sections() {
+    let channel = |name: &str, last_message_ts: i64, num_members: i32, is_old: bool, is_small: bool, is_member: bool| ChannelData {
+      id: name.to_uppercase(),
+      name: name.to_string(),
+      last_message_ts,
+      last_message_relevant: true,
+      num_members,
+      is_old,
+      is_small,
+      is_private: !is_member,
+      is_member,
+      is_readable: is_member,
+      ..ChannelData::default()
+    };
+    let data = vec![
+      channel("active", 300, 10, false, false, true),
+      channel("quiet-b", 100, 5, true, false, true),
+      channel("tiny", 300, 1, false, true, true),
+      channel("quiet-a", 200, 4, true, false, true),
+      channel("secret", 0, 2, false, true, false),
+      channel("quiet-tiny", 100, 2, true, true, true),
+    ];
+
+    let bot = ArchiveBot::default();
+    let sections: Vec<(Section, Vec<&str>)> = bot.sections(&data).into_iter()
+      .map(|(section, channels)| (section, channels.iter().map(|c| c.name.as_str()).collect()))
+      .collect();
+    assert_eq!(sections, vec![
+      (Section::Stale, vec!["quiet-b", "quiet-a"]),
+      (Section::Small, vec!["tiny"]),
+      (Section::StaleAndSmall, vec!["quiet-tiny"]),
+      (Section::Private, vec!["secret"]),
+    ]);
+
+    let bot = ArchiveBot { sort_by: vec![SortKey::Name], ..ArchiveBot::default() };
+    assert_eq!(bot.sections(&data)[0].1.iter().map(|c| c.name.as_str()).collect::<Vec<_>>(), vec!["quiet-a", "quiet-b"]);
+    let bot = ArchiveBot { sort_by: vec![SortKey::Members, SortKey::Name], ..ArchiveBot::default() };
+    assert_eq!(bot.sections(&data)[0].1.iter().map(|c| c.name.as_str()).collect::<Vec<_>>(), vec!["quiet-a", "quiet-b"]);
+  }
+
+  /// Test each policy for channels shared with other organizations.
+  #[test]
+  #[cfg(feature = "unit")]
+  fn test_shared_channels() {
+    let channel = |name: &str, is_old: bool, is_ext_shared: bool, is_pending_ext_shared: bool| ChannelData {
+      id: name.to_uppercase(),
+      name: name.to_string(),
+      last_message_ts: 100,
+      last_message_relevant: true,
+      num_members: 10,
+      is_old,
+      is_ext_shared,
+      is_pending_ext_shared,
+      is_member: true,
+      is_readable: true,
+      ..ChannelData::default()
+    };
+    let data = vec![
+      channel("quiet", true, false, false),
+      channel("partner-quiet", true, true, false),
+      channel("partner-active", false, true, false),
+      channel("invited", false, false, true),
+    ];
+    let sections = |policy: SharedChannelPolicy| {
+      let bot = ArchiveBot { shared_channels: policy, ..ArchiveBot::default() };
+      bot.sections(&data).into_iter()
+        .map(|(section, channels)| (section, channels.iter().map(|c| c.name.as_str()).collect::<Vec<_>>()))
+        .collect::<Vec<_>>()
+    };
+
+    assert_eq!(sections(SharedChannelPolicy::Separate), vec![
+      (Section::Stale, vec!["quiet"]),
+      (Section::Shared, vec!["partner-quiet"]),
+      (Section::PendingShared, vec!["invited"]),
+    ]);
+    assert_eq!(sections(SharedChannelPolicy::Include)[0], (Section::Stale, vec!["partner-quiet", "quiet"]));
+    assert_eq!(sections(SharedChannelPolicy::Exclude), vec![
+      (Section::Stale, vec!["quiet"]),
+      (Section::PendingShared, vec!["invited"]),
+    ]);
+
+    let shared = Channel { id: "C01".to_string(), is_ext_shared: true, ..Channel::default() };
+    let bot = ArchiveBot { shared_channels: SharedChannelPolicy::NeverArchive, ..ArchiveBot::default() };
+    assert!(!bot.may_archive(&shared));
+    assert!(bot.may_archive(&Channel { id: "C02".to_string(), ..Channel::default() }));
+    let bot = ArchiveBot { shared_channels: SharedChannelPolicy::Include, ..ArchiveBot::default() };
+    assert!(bot.may_archive(&shared));
+    let bot = ArchiveBot { shared_channels: SharedChannelPolicy::Separate, ..ArchiveBot::default() };
+    assert!(!bot.may_archive(&shared));
+    assert!(!bot.may_archive(&Channel { id: "C03".to_string(), is_pending_ext_shared: true, ..Channel::default() }));
+    assert!(bot.may_archive(&Channel { id: "C04".to_string(), is_org_shared: true, is_shared: true, ..Channel::default() }));
+    assert_eq!(ArchiveBot::default().shared_channels, SharedChannelPolicy::Include);
+
+    let message = bot.create_message(bot.catalog(""), &data, None, 0);
+    assert!(message.contains("*Shared with other organizations* (1)"));
+    assert!(message.contains("*Pending invitations* (1)"));
+  }
 }
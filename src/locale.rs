@@ -0,0 +1,325 @@
+use crate::Locale;
+
+/// Singular and plural forms of a message.
+#[derive(Debug, Clone, Copy)]
+pub struct Plural {
+  pub one: &'static str,
+  pub other: &'static str,
+}
+
+/// Catalog of messages for a locale.
+/// Messages are templates; see `template::render`.
+#[derive(Debug)]
+pub struct Catalog {
+  /// Whether a count takes the singular form.
+  pub is_one: fn(i64) -> bool,
+  pub message_headers: &'static [&'static str],
+  pub secondary_message_headers: &'static [&'static str],
+  pub creator_message_headers: &'static [&'static str],
+  /// Variables: `count`.
+  pub members: Plural,
+  /// Variables: `count`.
+  pub members_small: Plural,
+  pub private_unreadable: &'static str,
+  /// For public channels the bot hasn't joined, and so can't read.
+  pub history_unknown: &'static str,
+  /// For channels whose history couldn't be read because of an error.
+  pub history_failed: &'static str,
+  pub no_recent_messages: &'static str,
+  /// Variables: `date`.
+  pub no_messages_since_created: &'static str,
+  /// Variables: `date`.
+  pub last_event: &'static str,
+  /// Variables: `date`.
+  pub last_message: &'static str,
+  /// As `no_messages_since_created`, with `date` as how long ago, e.g. "43 days ago".
+  pub no_messages_since_created_ago: &'static str,
+  /// As `last_event`, with `date` as how long ago.
+  pub last_event_ago: &'static str,
+  /// As `last_message`, with `date` as how long ago.
+  pub last_message_ago: &'static str,
+  pub reason_stale_and_small: &'static str,
+  pub reason_stale: &'static str,
+  pub reason_small: &'static str,
+  pub reason_private: &'static str,
+  pub reason_pending_shared: &'static str,
+  pub section_stale: &'static str,
+  pub section_small: &'static str,
+  pub section_stale_and_small: &'static str,
+  pub section_shared: &'static str,
+  pub section_pending_shared: &'static str,
+  pub section_private: &'static str,
+  /// Variables: `count`, `sections`.
+  pub summary: Plural,
+  /// Variables: `count`, `workspaces`.
+  pub workspaces_summary: Plural,
+  /// Variables: `name`, `count`, `sections`.
+  pub workspace_line: Plural,
+  /// Variables: `name`.
+  pub workspace_clean: &'static str,
+  /// Variables: `name`.
+  pub workspace_failed: &'static str,
+  /// Variables: `invite`.
+  pub private_intro: &'static str,
+  /// Used in place of `/invite @name` when the bot's name is unknown.
+  pub invite_fallback: &'static str,
+  /// Used in place of `private_intro` on platforms without an invite command.
+  pub private_intro_permissions: &'static str,
+  /// Variables: `channel_id`, `reason`, `members`, `activity`.
+  pub creator_line: &'static str,
+  pub creator_footer: &'static str,
+  /// Variables: `header`, `notification_channel_id`.
+  pub secondary: &'static str,
+  pub unparsable_timestamp: &'static str,
+  /// Fallback date format for clients that can't display Slack dates; see `chrono::format::strftime`.
+  pub date_format: &'static str,
+  /// Variables: `count`.
+  pub days_ago: Plural,
+  pub today: &'static str,
+}
+
+impl Catalog {
+  /// Pick the singular or plural form of a message for a count.
+  pub fn plural(&self, plural: &Plural, count: i64) -> &'static str {
+    match (self.is_one)(count) {
+      true => plural.one,
+      false => plural.other,
+    }
+  }
+}
+
+impl Locale {
+  /// Message catalog for the locale.
+  pub fn catalog(&self) -> &'static Catalog {
+    match self {
+      Locale::En => &EN,
+      Locale::De => &DE,
+      Locale::Ja => &JA,
+    }
+  }
+}
+
+/// English.
+static EN: Catalog = Catalog {
+  is_one: |n| n == 1,
+  message_headers: &[
+    "Hey, you've got some cleaning up to do!",
+    "Hey boss, take a look at these, will ya?",
+    "I don't know what this is, or what to do with it:",
+  ],
+  secondary_message_headers: &[
+    "Hey folks! I, uh... made a list for you. Of channels. That you should archive. Maybe.",
+    "Hey everyone! If you want the satisfaction of crossing a task off your list, I have one!",
+    "BEEP, BOOP! Archival update: List generated. End of program.",
+  ],
+  creator_message_headers: &[
+    "Hi! I'm going through old and quiet channels, and found some you created:",
+    "Hey there! A few channels you created could use some attention:",
+  ],
+  members: Plural {
+    one: "has {{count}} member.",
+    other: "has {{count}} members.",
+  },
+  members_small: Plural {
+    one: "has *{{count}} member*.",
+    other: "has *{{count}} members*.",
+  },
+  private_unreadable: "The channel is private, so I can't read the latest message.",
+  history_unknown: "I haven't joined it, so I can't read the latest message.",
+  history_failed: "I couldn't read its history, so I don't know when the last message was.",
+  no_recent_messages: "No recent messages.",
+  no_messages_since_created: "There have been no messages since it was created on {{date}}.",
+  last_event: "The last event was on {{date}}, but there are no recent messages.",
+  last_message: "The last message was on {{date}}.",
+  no_messages_since_created_ago: "There have been no messages since it was created {{date}}.",
+  last_event_ago: "The last event was {{date}}, but there are no recent messages.",
+  last_message_ago: "The last message was {{date}}.",
+  reason_stale_and_small: "it's small and hasn't been active recently",
+  reason_stale: "it hasn't been active recently",
+  reason_small: "it's small",
+  reason_private: "it's private and I can't read it",
+  reason_pending_shared: "its invitation to another organization was never accepted",
+  section_stale: "Stale",
+  section_small: "Small",
+  section_stale_and_small: "Stale and small",
+  section_shared: "Shared with other organizations",
+  section_pending_shared: "Pending invitations",
+  section_private: "Private / unreadable",
+  summary: Plural {
+    one: "I found {{count}} channel to look at: {{sections}}. Details are in the thread.",
+    other: "I found {{count}} channels to look at: {{sections}}. Details are in the thread.",
+  },
+  workspaces_summary: Plural {
+    one: "Across {{workspaces}} workspaces, I found {{count}} channel to look at:",
+    other: "Across {{workspaces}} workspaces, I found {{count}} channels to look at:",
+  },
+  workspace_line: Plural {
+    one: "*{{name}}*: {{count}} channel: {{sections}}.",
+    other: "*{{name}}*: {{count}} channels: {{sections}}.",
+  },
+  workspace_clean: "*{{name}}*: nothing to look at.",
+  workspace_failed: "*{{name}}*: I couldn't check this workspace.",
+  private_intro: "I can't see into these private channels. To include them in updates, run {{invite}} in each:",
+  invite_fallback: "`/invite` me",
+  private_intro_permissions: "I can't see into these private channels. To include them in updates, give me permission to view each:",
+  creator_line: "<#{{channel_id}}> was flagged because {{reason}}. It {{members}} {{activity}}",
+  creator_footer: "If you'd like to keep a channel, post in it to let everyone know it's still in use. Otherwise, please consider archiving it.",
+  secondary: "{{header}} See <#{{notification_channel_id}}> for details.",
+  unparsable_timestamp: "[unable to parse timestamp]",
+  date_format: "%b %d, %Y %Z",
+  days_ago: Plural {
+    one: "{{count}} day ago",
+    other: "{{count}} days ago",
+  },
+  today: "today",
+};
+
+/// German.
+static DE: Catalog = Catalog {
+  is_one: |n| n == 1,
+  message_headers: &[
+    "Hey, hier gibt es etwas aufzuräumen!",
+    "Hallo Chef, wirf doch mal einen Blick auf diese hier:",
+    "Ich weiß nicht, was das ist oder was ich damit machen soll:",
+  ],
+  secondary_message_headers: &[
+    "Hallo zusammen! Ich habe eine Liste gemacht. Von Channels. Die ihr archivieren solltet. Vielleicht.",
+    "Hallo alle! Wer gerne Aufgaben abhakt, für den habe ich eine!",
+    "PIEP, PIEP! Archiv-Update: Liste erstellt. Programmende.",
+  ],
+  creator_message_headers: &[
+    "Hallo! Ich gehe alte und ruhige Channels durch und habe einige gefunden, die du erstellt hast:",
+    "Hey! Ein paar deiner Channels könnten etwas Aufmerksamkeit gebrauchen:",
+  ],
+  members: Plural {
+    one: "hat {{count}} Mitglied.",
+    other: "hat {{count}} Mitglieder.",
+  },
+  members_small: Plural {
+    one: "hat *{{count}} Mitglied*.",
+    other: "hat *{{count}} Mitglieder*.",
+  },
+  private_unreadable: "Der Channel ist privat, daher kann ich die letzte Nachricht nicht lesen.",
+  history_unknown: "Ich bin ihm nicht beigetreten, daher kann ich die letzte Nachricht nicht lesen.",
+  history_failed: "Ich konnte den Verlauf nicht lesen, daher weiß ich nicht, wann die letzte Nachricht war.",
+  no_recent_messages: "Keine aktuellen Nachrichten.",
+  no_messages_since_created: "Seit der Erstellung am {{date}} gab es keine Nachrichten.",
+  last_event: "Das letzte Ereignis war am {{date}}, aber es gibt keine aktuellen Nachrichten.",
+  last_message: "Die letzte Nachricht war am {{date}}.",
+  no_messages_since_created_ago: "Seit der Erstellung {{date}} gab es keine Nachrichten.",
+  last_event_ago: "Das letzte Ereignis war {{date}}, aber es gibt keine aktuellen Nachrichten.",
+  last_message_ago: "Die letzte Nachricht war {{date}}.",
+  reason_stale_and_small: "er klein ist und in letzter Zeit nicht aktiv war",
+  reason_stale: "er in letzter Zeit nicht aktiv war",
+  reason_small: "er klein ist",
+  reason_private: "er privat ist und ich ihn nicht lesen kann",
+  reason_pending_shared: "seine Einladung an eine andere Organisation nie angenommen wurde",
+  section_stale: "Inaktiv",
+  section_small: "Klein",
+  section_stale_and_small: "Inaktiv und klein",
+  section_shared: "Mit anderen Organisationen geteilt",
+  section_pending_shared: "Offene Einladungen",
+  section_private: "Privat / nicht lesbar",
+  summary: Plural {
+    one: "Ich habe {{count}} Channel zum Durchsehen gefunden: {{sections}}. Details stehen im Thread.",
+    other: "Ich habe {{count}} Channels zum Durchsehen gefunden: {{sections}}. Details stehen im Thread.",
+  },
+  workspaces_summary: Plural {
+    one: "In {{workspaces}} Workspaces habe ich {{count}} Channel zum Durchsehen gefunden:",
+    other: "In {{workspaces}} Workspaces habe ich {{count}} Channels zum Durchsehen gefunden:",
+  },
+  workspace_line: Plural {
+    one: "*{{name}}*: {{count}} Channel: {{sections}}.",
+    other: "*{{name}}*: {{count}} Channels: {{sections}}.",
+  },
+  workspace_clean: "*{{name}}*: nichts zu tun.",
+  workspace_failed: "*{{name}}*: Diesen Workspace konnte ich nicht prüfen.",
+  private_intro: "In diese privaten Channels kann ich nicht hineinsehen. Damit sie in Updates erscheinen, führe in jedem {{invite}} aus:",
+  invite_fallback: "`/invite` mit meinem Namen",
+  private_intro_permissions: "In diese privaten Channels kann ich nicht hineinsehen. Damit sie in Updates erscheinen, gib mir in jedem die Berechtigung, ihn zu sehen:",
+  creator_line: "<#{{channel_id}}> wurde markiert, weil {{reason}}. Er {{members}} {{activity}}",
+  creator_footer: "Wenn du einen Channel behalten möchtest, schreib etwas hinein, damit alle wissen, dass er noch genutzt wird. Andernfalls archiviere ihn bitte.",
+  secondary: "{{header}} Details findet ihr in <#{{notification_channel_id}}>.",
+  unparsable_timestamp: "[Zeitstempel nicht lesbar]",
+  date_format: "%d.%m.%Y %Z",
+  days_ago: Plural {
+    one: "vor {{count}} Tag",
+    other: "vor {{count}} Tagen",
+  },
+  today: "heute",
+};
+
+/// Japanese.
+static JA: Catalog = Catalog {
+  is_one: |_| false,
+  message_headers: &[
+    "片付けが必要なチャンネルがあります！",
+    "こちらを確認してもらえますか？",
+    "これが何なのか、どうすればいいのか分かりません：",
+  ],
+  secondary_message_headers: &[
+    "みなさん、アーカイブした方がよさそうなチャンネルのリストを作りました。たぶん。",
+    "タスクを片付ける達成感を味わいたい方へ、リストがあります！",
+    "ピポパ！アーカイブ更新：リストを作成しました。プログラム終了。",
+  ],
+  creator_message_headers: &[
+    "こんにちは！古くて静かなチャンネルを確認していたところ、あなたが作成したチャンネルが見つかりました：",
+    "あなたが作成したチャンネルのいくつかを確認してください：",
+  ],
+  members: Plural {
+    one: "メンバーは{{count}}人です。",
+    other: "メンバーは{{count}}人です。",
+  },
+  members_small: Plural {
+    one: "メンバーは*{{count}}人*です。",
+    other: "メンバーは*{{count}}人*です。",
+  },
+  private_unreadable: "プライベートチャンネルのため、最新のメッセージを読めません。",
+  history_unknown: "参加していないため、最新のメッセージを読めません。",
+  history_failed: "履歴を読み込めなかったため、最新のメッセージが分かりません。",
+  no_recent_messages: "最近のメッセージはありません。",
+  no_messages_since_created: "{{date}}に作成されてから、メッセージはありません。",
+  last_event: "最後のイベントは{{date}}ですが、最近のメッセージはありません。",
+  last_message: "最後のメッセージは{{date}}です。",
+  no_messages_since_created_ago: "{{date}}に作成されてから、メッセージはありません。",
+  last_event_ago: "最後のイベントは{{date}}ですが、最近のメッセージはありません。",
+  last_message_ago: "最後のメッセージは{{date}}です。",
+  reason_stale_and_small: "小規模で、最近活動がない",
+  reason_stale: "最近活動がない",
+  reason_small: "小規模な",
+  reason_private: "プライベートで読めない",
+  reason_pending_shared: "他の組織への招待が承認されていない",
+  section_stale: "停滞",
+  section_small: "小規模",
+  section_stale_and_small: "停滞かつ小規模",
+  section_shared: "他の組織と共有",
+  section_pending_shared: "保留中の招待",
+  section_private: "プライベート / 読み取り不可",
+  summary: Plural {
+    one: "確認が必要なチャンネルが{{count}}件見つかりました：{{sections}}。詳細はスレッドをご覧ください。",
+    other: "確認が必要なチャンネルが{{count}}件見つかりました：{{sections}}。詳細はスレッドをご覧ください。",
+  },
+  workspaces_summary: Plural {
+    one: "{{workspaces}}個のワークスペースで、確認が必要なチャンネルが{{count}}件見つかりました：",
+    other: "{{workspaces}}個のワークスペースで、確認が必要なチャンネルが{{count}}件見つかりました：",
+  },
+  workspace_line: Plural {
+    one: "*{{name}}*：{{count}}件（{{sections}}）",
+    other: "*{{name}}*：{{count}}件（{{sections}}）",
+  },
+  workspace_clean: "*{{name}}*：確認が必要なチャンネルはありません。",
+  workspace_failed: "*{{name}}*：このワークスペースを確認できませんでした。",
+  private_intro: "これらのプライベートチャンネルの中を見ることができません。更新に含めるには、各チャンネルで{{invite}}を実行してください：",
+  invite_fallback: "私宛ての`/invite`",
+  private_intro_permissions: "これらのプライベートチャンネルの中を見ることができません。更新に含めるには、各チャンネルで私に閲覧権限を付与してください：",
+  creator_line: "<#{{channel_id}}> は{{reason}}ため、フラグが付けられました。{{members}}{{activity}}",
+  creator_footer: "チャンネルを残したい場合は、まだ使われていることが伝わるように何か投稿してください。そうでなければ、アーカイブをご検討ください。",
+  secondary: "{{header}} 詳細は<#{{notification_channel_id}}>をご覧ください。",
+  unparsable_timestamp: "[タイムスタンプを解析できません]",
+  date_format: "%Y/%m/%d %Z",
+  days_ago: Plural {
+    one: "{{count}}日前",
+    other: "{{count}}日前",
+  },
+  today: "今日",
+};
@@ -0,0 +1,465 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+use std::time::Instant;
+use futures::future;
+use reqwest::{Method, RequestBuilder};
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use serde_json::json;
+use tokio::sync::OnceCell;
+use tracing::{field, info_span, Instrument, Span};
+
+use crate::error::MattermostError;
+use crate::platform::{translate_markup, ChatPlatform};
+use crate::types::{Channel, Message, Timestamp, User};
+
+/// Largest page Mattermost returns.
+const PER_PAGE: usize = 200;
+
+/// Mattermost's REST API, v4. Manages the channels of a single team.
+///
+/// The token is a bot or personal access token. Messages are written in Slack's markup, and
+/// translated to Mattermost's Markdown when posted.
+#[derive(Debug)]
+pub struct Mattermost {
+  /// Server URL, e.g. `https://mattermost.example.com`.
+  pub url: String,
+  /// Id of the team whose channels to manage.
+  pub team_id: String,
+  /// The bot's own user id, fetched when first needed.
+  user_id: OnceCell<String>,
+  /// Channel names by id, to link to channels in messages.
+  names: Mutex<HashMap<String, String>>,
+}
+
+/// Channel response. Non-comprehensive.
+#[derive(Debug, Deserialize)]
+struct MattermostChannel {
+  id: String,
+  name: String,
+  #[serde(rename = "type")]
+  channel_type: String,
+  #[serde(default)]
+  create_at: i64,
+  #[serde(default)]
+  creator_id: String,
+}
+
+/// Channel stats response.
+#[derive(Debug, Deserialize)]
+struct ChannelStats {
+  member_count: i32,
+}
+
+/// Channel member response. Non-comprehensive.
+#[derive(Debug, Deserialize)]
+struct ChannelMember {
+  user_id: String,
+}
+
+/// Post response. Non-comprehensive.
+#[derive(Debug, Deserialize)]
+struct Post {
+  id: String,
+  channel_id: String,
+  #[serde(default)]
+  user_id: String,
+  #[serde(default)]
+  message: String,
+  #[serde(rename = "type", default)]
+  post_type: String,
+  create_at: i64,
+  #[serde(default)]
+  props: HashMap<String, serde_json::Value>,
+}
+
+/// Post list response, e.g. a channel's history.
+#[derive(Debug, Deserialize)]
+struct PostList {
+  order: Vec<String>,
+  posts: HashMap<String, Post>,
+}
+
+/// User response. Non-comprehensive.
+#[derive(Debug, Deserialize)]
+struct MattermostUser {
+  id: String,
+  username: String,
+  #[serde(default)]
+  is_bot: bool,
+  #[serde(default)]
+  delete_at: i64,
+  /// Space-separated, e.g. "system_user" or "system_guest".
+  #[serde(default)]
+  roles: String,
+}
+
+/// File upload response.
+#[derive(Debug, Deserialize)]
+struct FileUpload {
+  file_infos: Vec<FileInfo>,
+}
+
+/// Uploaded file. Non-comprehensive.
+#[derive(Debug, Deserialize)]
+struct FileInfo {
+  id: String,
+}
+
+/// Error response.
+#[derive(Debug, Default, Deserialize)]
+struct ErrorResponse {
+  #[serde(default)]
+  id: String,
+  #[serde(default)]
+  message: String,
+}
+
+impl Mattermost {
+  /// Connect to a server, to manage a team's channels.
+  pub fn new(url: &str, team_id: &str) -> Mattermost {
+    Mattermost {
+      url: url.trim_end_matches('/').to_string(),
+      team_id: team_id.to_string(),
+      user_id: OnceCell::new(),
+      names: Mutex::new(HashMap::new()),
+    }
+  }
+
+  /// Start an authenticated API request.
+  fn request(&self, token: &str, method: Method, path: &str) -> RequestBuilder {
+    reqwest::Client::new()
+      .request(method, format!("{}/api/v4{}", self.url, path))
+      .bearer_auth(token)
+  }
+
+  /// Send a request and parse the response.
+  /// Each request is traced in a `mattermost_api` span, with its path, HTTP status, retries and duration.
+  /// Mattermost requests are never retried, so `retries` is always 0.
+  async fn send<T: DeserializeOwned>(&self, request: RequestBuilder) -> Result<T, MattermostError> {
+    let (client, request) = request.build_split();
+    let request = request.map_err(MattermostError::Client)?;
+    let method = request.method().to_string();
+    let path = request.url().path().to_string();
+    let span = info_span!("mattermost_api", method, path, status = field::Empty, retries = 0, duration_ms = field::Empty);
+    let start = Instant::now();
+    let result = async {
+      let response = client.execute(request).await.map_err(MattermostError::Client)?;
+      let status = response.status();
+      Span::current().record("status", status.as_u16());
+      let body = response.text().await.map_err(MattermostError::Client)?;
+      if !status.is_success() {
+        let error: ErrorResponse = serde_json::from_str(&body).unwrap_or_default();
+        return Err(MattermostError::Api { status: status.as_u16(), id: error.id, message: error.message });
+      }
+      serde_json::from_str(&body).map_err(|e| MattermostError::MalformedResponse(body, e))
+    }.instrument(span.clone()).await;
+    span.record("duration_ms", start.elapsed().as_millis() as u64);
+    result
+  }
+
+  /// Get every page of a paginated list.
+  async fn paginate<T: DeserializeOwned>(&self, token: &str, path: &str, query: &[(&str, &str)]) -> Result<Vec<T>, MattermostError> {
+    let mut all: Vec<T> = vec![];
+    for page in 0.. {
+      let request = self.request(token, Method::GET, path)
+        .query(query)
+        .query(&[("page", page.to_string()), ("per_page", PER_PAGE.to_string())]);
+      let items: Vec<T> = self.send(request).await?;
+      let last = items.len() < PER_PAGE;
+      all.extend(items);
+      if last {
+        break;
+      }
+    }
+    Ok(all)
+  }
+
+  /// The bot's own user id.
+  async fn user_id(&self, token: &str) -> Result<&str, MattermostError> {
+    self.user_id.get_or_try_init(|| async {
+      let me: MattermostUser = self.send(self.request(token, Method::GET, "/users/me")).await?;
+      Ok(me.id)
+    }).await.map(String::as_str)
+  }
+
+  /// Names of the channels linked to in a message, fetching any that weren't already listed.
+  async fn linked_names(&self, token: &str, text: &str) -> HashMap<String, String> {
+    let ids: HashSet<&str> = text.split("<#").skip(1).filter_map(|link| link.split(['>', '|']).next()).collect();
+    let missing: Vec<&str> = {
+      let names = self.names.lock().unwrap();
+      ids.into_iter().filter(|id| !names.contains_key(*id)).collect()
+    };
+    for id in missing {
+      let path = format!("/channels/{}", id);
+      if let Ok(channel) = self.send::<MattermostChannel>(self.request(token, Method::GET, &path)).await {
+        self.names.lock().unwrap().insert(channel.id, channel.name);
+      }
+    }
+    self.names.lock().unwrap().clone()
+  }
+}
+
+impl ChatPlatform for Mattermost {
+  type Error = MattermostError;
+
+  async fn channels(&self, token: &str) -> Result<Vec<Channel>, MattermostError> {
+    let public: Vec<MattermostChannel> = self.paginate(token, &format!("/teams/{}/channels", self.team_id), &[]).await?;
+    let request = self.request(token, Method::GET, &format!("/users/me/teams/{}/channels", self.team_id));
+    let joined: Vec<MattermostChannel> = self.send(request).await?;
+
+    let member_of: HashSet<String> = joined.iter().map(|c| c.id.clone()).collect();
+    let mut channels: Vec<MattermostChannel> = joined;
+    channels.extend(public.into_iter().filter(|c| !member_of.contains(&c.id)));
+    // Direct and group messages aren't channels.
+    channels.retain(|c| c.channel_type == "O" || c.channel_type == "P");
+
+    let stats = future::join_all(channels.iter().map(|c| {
+      self.send::<ChannelStats>(self.request(token, Method::GET, &format!("/channels/{}/stats", c.id)))
+    })).await;
+
+    let mut names = self.names.lock().unwrap();
+    channels.into_iter().zip(stats).map(|(c, stats)| {
+      names.insert(c.id.clone(), c.name.clone());
+      Ok(Channel {
+        is_channel: c.channel_type == "O",
+        is_private: c.channel_type == "P",
+        is_member: member_of.contains(&c.id),
+        created: Some(Timestamp::new(c.create_at / 1000)),
+        creator: Some(c.creator_id).filter(|id| !id.is_empty()),
+        num_members: stats?.member_count,
+        id: c.id,
+        name: c.name,
+        ..Channel::default()
+      })
+    }).collect()
+  }
+
+  async fn history(&self, token: &str, channel_id: &str, limit: u16) -> Result<Option<Vec<Message>>, MattermostError> {
+    let request = self.request(token, Method::GET, &format!("/channels/{}/posts", channel_id))
+      .query(&[("per_page", limit.to_string())]);
+
+    let mut list: PostList = match self.send(request).await {
+      Ok(list) => list,
+      Err(MattermostError::Api { status: 403, .. }) => return Ok(None),
+      Err(e) => return Err(e),
+    };
+    Ok(Some(list.order.iter().filter_map(|id| list.posts.remove(id)).map(Message::from).collect()))
+  }
+
+  async fn members(&self, token: &str, channel_id: &str) -> Result<Vec<String>, MattermostError> {
+    let members: Vec<ChannelMember> = self.paginate(token, &format!("/channels/{}/members", channel_id), &[]).await?;
+    Ok(members.into_iter().map(|m| m.user_id).collect())
+  }
+
+  async fn users(&self, token: &str) -> Result<Vec<User>, MattermostError> {
+    let users: Vec<MattermostUser> = self.paginate(token, "/users", &[("in_team", &self.team_id)]).await?;
+    Ok(users.into_iter().map(User::from).collect())
+  }
+
+  async fn user(&self, token: &str, user_id: &str) -> Result<User, MattermostError> {
+    let user: MattermostUser = self.send(self.request(token, Method::GET, &format!("/users/{}", user_id))).await?;
+    Ok(user.into())
+  }
+
+  async fn bot_name(&self, token: &str) -> Result<String, MattermostError> {
+    let me: MattermostUser = self.send(self.request(token, Method::GET, "/users/me")).await?;
+    Ok(me.username)
+  }
+
+  async fn join(&self, token: &str, channel_id: &str) -> Result<(), MattermostError> {
+    let user_id = self.user_id(token).await?;
+    let request = self.request(token, Method::POST, &format!("/channels/{}/members", channel_id))
+      .json(&json!({ "user_id": user_id }));
+    self.send::<serde_json::Value>(request).await.map(|_| ())
+  }
+
+  async fn leave(&self, token: &str, channel_id: &str) -> Result<(), MattermostError> {
+    let request = self.request(token, Method::DELETE, &format!("/channels/{}/members/me", channel_id));
+    self.send::<serde_json::Value>(request).await.map(|_| ())
+  }
+
+  async fn archive(&self, token: &str, channel_id: &str) -> Result<(), MattermostError> {
+    let request = self.request(token, Method::DELETE, &format!("/channels/{}", channel_id));
+    self.send::<serde_json::Value>(request).await.map(|_| ())
+  }
+
+  async fn post(&self, token: &str, channel_id: &str, thread_id: Option<&str>, text: &str) -> Result<String, MattermostError> {
+    let names = self.linked_names(token, text).await;
+    let request = self.request(token, Method::POST, "/posts")
+      .json(&json!({
+        "channel_id": channel_id,
+        "root_id": thread_id.unwrap_or(""),
+        "message": translate_markup(text, |id| names.get(id).map(|name| format!("~{}", name)), |_, _, fallback| Some(fallback.to_string())),
+      }));
+    let post: Post = self.send(request).await?;
+    Ok(post.id)
+  }
+
+  async fn open_direct_message(&self, token: &str, user_id: &str) -> Result<String, MattermostError> {
+    let bot_id = self.user_id(token).await?;
+    let request = self.request(token, Method::POST, "/channels/direct")
+      .json(&json!([bot_id, user_id]));
+    let channel: MattermostChannel = self.send(request).await?;
+    Ok(channel.id)
+  }
+
+  async fn upload_file(&self, token: &str, channel_id: &str, filename: &str, content: &[u8]) -> Result<(), MattermostError> {
+    let request = self.request(token, Method::POST, "/files")
+      .query(&[("channel_id", channel_id), ("filename", filename)])
+      .body(content.to_vec());
+    let upload: FileUpload = self.send(request).await?;
+
+    let request = self.request(token, Method::POST, "/posts")
+      .json(&json!({
+        "channel_id": channel_id,
+        "message": "",
+        "file_ids": upload.file_infos.iter().map(|f| f.id.as_str()).collect::<Vec<&str>>(),
+      }));
+    self.send::<Post>(request).await.map(|_| ())
+  }
+}
+
+impl From<Post> for Message {
+  fn from(post: Post) -> Message {
+    let from_bot = post.props.get("from_bot").is_some_and(|v| v.as_bool() == Some(true) || v.as_str() == Some("true"));
+    // Match Slack's subtypes, so the same posts are ignored.
+    let subtype = match post.post_type.as_str() {
+      _ if from_bot => "bot_message",
+      "system_join_channel" | "system_add_to_channel" => "channel_join",
+      "system_leave_channel" | "system_remove_from_channel" => "channel_leave",
+      "system_header_change" => "channel_topic",
+      "system_purpose_change" => "channel_purpose",
+      "system_displayname_change" => "channel_name",
+      "system_channel_deleted" => "channel_archive",
+      "system_channel_restored" => "channel_unarchive",
+      other => other,
+    };
+    Message {
+      event_type: "message".to_string(),
+      subtype: subtype.to_string(),
+      channel: Some(post.channel_id),
+      user: Some(post.user_id).filter(|id| !id.is_empty()),
+      text: Some(post.message),
+      ts: Some(Timestamp::new(post.create_at / 1000)),
+    }
+  }
+}
+
+impl From<MattermostUser> for User {
+  fn from(user: MattermostUser) -> User {
+    User {
+      id: user.id,
+      name: user.username,
+      deleted: user.delete_at > 0,
+      is_bot: user.is_bot,
+      is_guest: user.roles.split(' ').any(|role| role == "system_guest"),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  #[cfg(feature = "unit")]
+  use super::*;
+  #[cfg(feature = "unit")]
+  use crate::mock::{mock_server, SpanRecorder};
+
+  /// Test listing channels and reading history from a mock server.
+  #[tokio::test]
+  #[cfg(feature = "unit")]
+  async fn test_mattermost_channels() {
+    let (url, requests) = mock_server("/api/v4", vec![
+      ("GET /teams/T1/channels", 200, r#"[
+        {"id":"c1","name":"town-square","type":"O","create_at":1600000000000,"creator_id":""},
+        {"id":"c2","name":"old-project","type":"O","create_at":1600000000000,"creator_id":"u2"}
+      ]"#),
+      ("GET /users/me/teams/T1/channels", 200, r#"[
+        {"id":"c1","name":"town-square","type":"O","create_at":1600000000000},
+        {"id":"c3","name":"secret","type":"P","create_at":1600000000000},
+        {"id":"d1","name":"u1__u2","type":"D","create_at":1600000000000}
+      ]"#),
+      ("GET /channels/c1/stats", 200, r#"{"channel_id":"c1","member_count":12}"#),
+      ("GET /channels/c2/stats", 200, r#"{"channel_id":"c2","member_count":2}"#),
+      ("GET /channels/c3/stats", 200, r#"{"channel_id":"c3","member_count":4}"#),
+      ("GET /channels/c1/posts", 200, r#"{"order":["p2","p1"],"posts":{
+        "p1":{"id":"p1","channel_id":"c1","user_id":"u1","message":"hi","type":"","create_at":1700000000000},
+        "p2":{"id":"p2","channel_id":"c1","user_id":"u2","message":"u2 joined the channel.","type":"system_join_channel","create_at":1700000100000}
+      }}"#),
+      ("GET /channels/c2/posts", 403, r#"{"id":"api.context.permissions.app_error","message":"You do not have the appropriate permissions.","status_code":403}"#),
+    ]).await;
+    let mattermost = Mattermost::new(&format!("{}/", url), "T1");
+
+    let mut channels = mattermost.channels("token").await.unwrap();
+    channels.sort_by(|a, b| a.id.cmp(&b.id));
+    let summary: Vec<(&str, bool, bool, i32)> = channels.iter().map(|c| (c.id.as_str(), c.is_private, c.is_member, c.num_members)).collect();
+    assert_eq!(summary, vec![("c1", false, true, 12), ("c2", false, false, 2), ("c3", true, true, 4)]);
+    assert_eq!(channels[1].creator.as_deref(), Some("u2"));
+    assert_eq!(channels[1].created, Some(Timestamp::new(1600000000)));
+
+    let history = mattermost.history("token", "c1", 10).await.unwrap().unwrap();
+    assert_eq!(history.len(), 2);
+    assert!(history[0].ignore_type());
+    assert!(!history[1].ignore_type());
+    assert_eq!(history[1].ts, Some(Timestamp::new(1700000000)));
+    assert!(mattermost.history("token", "c2", 10).await.unwrap().is_none());
+
+    assert!(requests.lock().unwrap().iter().all(|r| !r.starts_with("POST")));
+  }
+
+  /// Test joining, posting and archiving against a mock server.
+  #[tokio::test]
+  #[cfg(feature = "unit")]
+  async fn test_mattermost_actions() {
+    let (url, requests) = mock_server("/api/v4", vec![
+      ("GET /users/me", 200, r#"{"id":"bot1","username":"archive-bot","is_bot":true}"#),
+      ("POST /channels/c2/members", 201, r#"{"channel_id":"c2","user_id":"bot1"}"#),
+      ("DELETE /channels/c2", 200, r#"{"status":"OK"}"#),
+      ("GET /channels/c2", 200, r#"{"id":"c2","name":"old-project","type":"O"}"#),
+      ("POST /posts", 201, r#"{"id":"p9","channel_id":"c0","message":"","create_at":1700000000000}"#),
+    ]).await;
+    let mattermost = Mattermost::new(&url, "T1");
+
+    mattermost.join("token", "c2").await.unwrap();
+    let id = mattermost.post("token", "c0", None, "<#c2> has *1 member*. Last message <!date^1700000000^{date_short}|2023-11-14>.").await.unwrap();
+    assert_eq!(id, "p9");
+    mattermost.post("token", "c0", Some("p9"), "reply").await.unwrap();
+    mattermost.archive("token", "c2").await.unwrap();
+    match mattermost.leave("token", "c9").await {
+      Err(MattermostError::Api { status: 404, .. }) => {},
+      result => panic!("unexpected result: {:?}", result),
+    }
+
+    let requests = requests.lock().unwrap();
+    assert_eq!(requests.iter().filter(|r| *r == "GET /users/me").count(), 1);
+    assert!(requests.contains(&r#"POST /channels/c2/members {"user_id":"bot1"}"#.to_string()));
+    let posts: Vec<serde_json::Value> = requests.iter()
+      .filter_map(|r| r.strip_prefix("POST /posts "))
+      .map(|body| serde_json::from_str(body).unwrap())
+      .collect();
+    assert_eq!(posts[0]["message"], "~old-project has **1 member**. Last message 2023-11-14.");
+    assert_eq!(posts[0]["root_id"], "");
+    assert_eq!(posts[1]["root_id"], "p9");
+    assert!(requests.contains(&"DELETE /channels/c2".to_string()));
+  }
+
+  /// Test that each request is traced in a `mattermost_api` span.
+  #[tokio::test]
+  #[cfg(feature = "unit")]
+  async fn test_mattermost_spans() {
+    let (url, _) = mock_server("/api/v4", vec![
+      ("GET /users/me", 200, r#"{"id":"bot1","username":"archive-bot","is_bot":true}"#),
+    ]).await;
+    let mattermost = Mattermost::new(&url, "T1");
+    let recorder = SpanRecorder::default();
+    let _guard = tracing::subscriber::set_default(recorder.clone());
+
+    mattermost.bot_name("token").await.unwrap();
+    assert!(mattermost.leave("token", "c9").await.is_err());
+
+    let spans = recorder.spans("mattermost_api");
+    assert_eq!(spans.len(), 2);
+    assert!(spans[0].starts_with("mattermost_api method=GET path=/api/v4/users/me retries=0 status=200 duration_ms="), "{}", spans[0]);
+    assert!(spans[1].starts_with("mattermost_api method=DELETE path=/api/v4/channels/c9/members/me retries=0 status=404 duration_ms="), "{}", spans[1]);
+  }
+}
@@ -0,0 +1,376 @@
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use rand::seq::SliceRandom;
+
+use crate::{ArchiveBot, SharedChannelPolicy, SortKey};
+use crate::locale::Catalog;
+use crate::platform::ChatPlatform;
+use crate::template::{self, Vars};
+use crate::types::*;
+
+impl<P: ChatPlatform> ArchiveBot<P> {
+  /// Message catalog for messages posted to a channel.
+  pub(crate) fn catalog(&self, channel_id: &str) -> &'static Catalog {
+    self.channel_locales.iter()
+      .find(|(id, _)| *id == channel_id)
+      .map(|(_, locale)| *locale)
+      .unwrap_or(self.locale)
+      .catalog()
+  }
+
+  /// Parse data to create regular update message to post regarding channel status.
+  pub(crate) fn create_message(&self, c: &Catalog, data: &[ChannelData], bot_name: Option<&str>, now: i64) -> String {
+    let mut message: String = "".to_string();
+    for (section, channels) in self.sections(data) {
+      message.push_str(&format!("\n*{}* ({})\n", section.title(c), channels.len()));
+      if section == Section::Private {
+        message.push_str(&format!("{}\n", self.private_message(c, bot_name)));
+      }
+      for channel in channels {
+        // mrkdwn parsed, but no list format; using * breaks *bold* text
+        message.push_str(&format!("- {}\n", self.channel_message(c, channel, now)));
+      }
+    }
+
+    if !message.is_empty() {
+      let vars = self.update_vars(c, data);
+      message = format!("{}\n{}", self.render(self.templates.header, template::HEADER, &vars), message);
+      let footer = self.render(self.templates.footer, template::FOOTER, &vars);
+      if !footer.is_empty() {
+        message.push_str(&format!("\n{}", footer));
+      }
+    }
+
+    message
+  }
+
+  /// Create the summary message that starts a threaded update.
+  pub(crate) fn create_summary_message(&self, c: &Catalog, data: &[ChannelData]) -> String {
+    let prefix = self.render(self.templates.header, template::HEADER, &self.update_vars(c, data));
+    let (count, sections) = self.section_counts(c, data);
+    let vars: Vars = vec![
+      ("count", count.to_string()),
+      ("sections", sections),
+    ];
+    format!("{}\n{}", prefix, template::render(c.plural(&c.summary, count as i64), &vars))
+  }
+
+  /// Count the channels to include in an update, and list how many are in each section.
+  pub(crate) fn section_counts(&self, c: &Catalog, data: &[ChannelData]) -> (usize, String) {
+    let sections = self.sections(data);
+    let count: usize = sections.iter().map(|(_, channels)| channels.len()).sum();
+    let counts: Vec<String> = sections.iter()
+      .map(|(section, channels)| format!("{} ({})", section.title(c), channels.len()))
+      .collect();
+    (count, counts.join(", "))
+  }
+
+  /// Create one thread reply per channel, plus one for private channels the bot can't read.
+  pub(crate) fn create_thread_replies(&self, c: &Catalog, data: &[ChannelData], bot_name: Option<&str>, now: i64) -> Vec<String> {
+    let mut replies: Vec<String> = vec![];
+    for (section, channels) in self.sections(data) {
+      match section {
+        Section::Private => {
+          let mut reply = self.private_message(c, bot_name);
+          for channel in channels {
+            reply.push_str(&format!("\n- {}", self.channel_message(c, channel, now)));
+          }
+          replies.push(reply);
+        },
+        _ => replies.extend(channels.iter().map(|channel| self.channel_message(c, channel, now))),
+      }
+    }
+    replies
+  }
+
+  /// Create direct message to a channel creator regarding their flagged channels.
+  pub(crate) fn create_creator_message(&self, c: &Catalog, channels: &[&ChannelData], now: i64) -> String {
+    let mut message = pick(&self.creator_message_headers, c.creator_message_headers);
+    message.push('\n');
+    for channel in channels {
+      let vars: Vars = vec![
+        ("channel_id", channel.id.clone()),
+        ("reason", self.flag_reason(c, channel).to_string()),
+        ("members", self.members_message(c, channel)),
+        ("activity", self.time_message(c, channel, now)),
+      ];
+      message.push_str(&format!("- {}\n", template::render(c.creator_line, &vars)));
+    }
+    message.push_str(c.creator_footer);
+    message
+  }
+
+  /// Create secondary notification message.
+  pub(crate) fn create_secondary_message(&self, c: &Catalog) -> String {
+    let vars: Vars = vec![
+      ("header", pick(&self.secondary_message_headers, c.secondary_message_headers)),
+      ("notification_channel_id", self.notification_channel_id.clone()),
+    ];
+    self.render(self.templates.secondary, c.secondary, &vars)
+  }
+
+  /// Group channels to mention by section, sorted within each section.
+  pub(crate) fn sections<'a>(&self, data: &'a [ChannelData]) -> Vec<(Section, Vec<&'a ChannelData>)> {
+    let mut sections: BTreeMap<Section, Vec<&ChannelData>> = BTreeMap::new();
+    for channel in data {
+      if let Some(section) = self.channel_section(channel) {
+        sections.entry(section).or_default().push(channel);
+      }
+    }
+    for channels in sections.values_mut() {
+      channels.sort_by(|a, b| self.compare_channels(a, b));
+    }
+    sections.into_iter().collect()
+  }
+
+  /// Which section of the update a channel belongs in, if any.
+  pub(crate) fn channel_section(&self, channel: &ChannelData) -> Option<Section> {
+    let section = match channel {
+      ChannelData { is_ignored: true, .. } => None,
+      ChannelData { is_ext_shared: true, .. } if self.shared_channels == SharedChannelPolicy::Exclude => None,
+      _ if channel.is_unreadable() => Some(Section::Private),
+      ChannelData { is_new: true, .. } => None,
+      ChannelData { is_pending_ext_shared: true, .. } => Some(Section::PendingShared),
+      ChannelData { is_old: true, is_small: true, .. } => Some(Section::StaleAndSmall),
+      ChannelData { is_old: true, .. } => Some(Section::Stale),
+      ChannelData { is_small: true, .. } => Some(Section::Small),
+      _ => None,
+    };
+    match (section, channel.is_ext_shared, self.shared_channels) {
+      (Some(Section::Private), _, _) => section,
+      (Some(_), true, SharedChannelPolicy::Separate) => Some(Section::Shared),
+      _ => section,
+    }
+  }
+
+  /// Whether a channel should be included in updates.
+  pub(crate) fn channel_should_be_mentioned(&self, channel: &ChannelData) -> bool {
+    matches!(self.channel_section(channel), Some(section) if section != Section::Private)
+  }
+
+  /// Compare channels using the configured sort keys, in order.
+  fn compare_channels(&self, a: &ChannelData, b: &ChannelData) -> Ordering {
+    self.sort_by.iter().fold(Ordering::Equal, |ordering, key| {
+      ordering.then_with(|| match key {
+        SortKey::LastActivity => a.last_activity().cmp(&b.last_activity()),
+        SortKey::Members => a.num_members.cmp(&b.num_members),
+        SortKey::Name => a.name.cmp(&b.name),
+      })
+    })
+  }
+
+  /// Describe a single channel.
+  fn channel_message(&self, c: &Catalog, channel: &ChannelData, now: i64) -> String {
+    self.render(self.templates.line, template::LINE, &self.channel_vars(c, channel, now))
+  }
+
+  /// Render a configured template, or the default if none is configured.
+  fn render(&self, configured: Option<&str>, default: &str, vars: &Vars) -> String {
+    template::render(configured.unwrap_or(default), vars)
+  }
+
+  /// Template variables for the header and footer of an update.
+  fn update_vars(&self, c: &Catalog, data: &[ChannelData]) -> Vars<'static> {
+    let count = data.iter().filter(|channel| self.channel_section(channel).is_some()).count();
+    vec![
+      ("header", pick(&self.message_headers, c.message_headers)),
+      ("count", count.to_string()),
+    ]
+  }
+
+  /// Template variables for a single channel, with ages counted from `now`.
+  fn channel_vars(&self, c: &Catalog, channel: &ChannelData, now: i64) -> Vars<'static> {
+    let days_since = match channel.last_activity() {
+      0 => "".to_string(),
+      ts => ((now - ts) / (24 * 60 * 60)).to_string(),
+    };
+    let last_message_date = match channel.last_message_ts {
+      0 => "".to_string(),
+      _ => channel.last_message_ts_formatted(c, &self.dates, now),
+    };
+    vec![
+      ("channel_id", channel.id.clone()),
+      ("name", channel.name.clone()),
+      ("num_members", channel.num_members.to_string()),
+      ("last_message_date", last_message_date),
+      ("days_since", days_since),
+      ("reason", self.flag_reason(c, channel).to_string()),
+      ("section", self.channel_section(channel).map(|s| s.title(c)).unwrap_or("").to_string()),
+      ("members", self.members_message(c, channel)),
+      ("activity", self.time_message(c, channel, now)),
+      ("team_id", channel.team_id.clone().unwrap_or_default()),
+    ]
+  }
+
+  /// Explain that the bot can't read private channels it hasn't been invited to.
+  fn private_message(&self, c: &Catalog, bot_name: Option<&str>) -> String {
+    if !P::HAS_INVITE_COMMAND {
+      return c.private_intro_permissions.to_string();
+    }
+    let invite = match bot_name {
+      Some(name) => format!("`/invite @{}`", name),
+      None => c.invite_fallback.to_string(),
+    };
+    template::render(c.private_intro, &vec![("invite", invite)])
+  }
+
+  /// Describe the number of members in a channel.
+  fn members_message(&self, c: &Catalog, channel: &ChannelData) -> String {
+    let plural = match channel.is_small {
+      true => &c.members_small,
+      false => &c.members,
+    };
+    let count = channel.num_members as i64;
+    template::render(c.plural(plural, count), &vec![("count", count.to_string())])
+  }
+
+  /// Describe the latest activity in a channel.
+  fn time_message(&self, c: &Catalog, channel: &ChannelData, now: i64) -> String {
+    let (since_created, last_event, last_message) = match self.dates.is_relative() {
+      true => (c.no_messages_since_created_ago, c.last_event_ago, c.last_message_ago),
+      false => (c.no_messages_since_created, c.last_event, c.last_message),
+    };
+    let (message, date) = match channel {
+      _ if channel.is_unreadable() => return c.private_unreadable.to_string(),
+      _ if channel.is_history_unknown() => return c.history_unknown.to_string(),
+      ChannelData { history_failed: true, .. } => return c.history_failed.to_string(),
+      ChannelData { last_message_ts: 0, created: 0, .. } => return c.no_recent_messages.to_string(),
+      ChannelData { last_message_ts: 0, .. } => (since_created, channel.created_formatted(c, &self.dates, now)),
+      ChannelData { last_message_relevant: false, .. } => (last_event, channel.last_message_ts_formatted(c, &self.dates, now)),
+      _ => (last_message, channel.last_message_ts_formatted(c, &self.dates, now)),
+    };
+    let date = match channel.is_old {
+      true => format!("*{}*", date),
+      false => date,
+    };
+    template::render(message, &vec![("date", date)])
+  }
+
+  /// Why a channel was flagged.
+  fn flag_reason(&self, c: &Catalog, channel: &ChannelData) -> &'static str {
+    match channel {
+      _ if channel.is_unreadable() => c.reason_private,
+      ChannelData { is_pending_ext_shared: true, .. } => c.reason_pending_shared,
+      ChannelData { is_old: true, is_small: true, .. } => c.reason_stale_and_small,
+      ChannelData { is_old: true, .. } => c.reason_stale,
+      _ => c.reason_small,
+    }
+  }
+}
+
+/// Pick a configured message at random, or one of the locale's defaults if none are configured.
+fn pick(configured: &[&'static str], defaults: &[&'static str]) -> String {
+  let messages = match configured.is_empty() {
+    true => defaults,
+    false => configured,
+  };
+  messages.choose(&mut rand::thread_rng()).copied().unwrap_or_default().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+  #[cfg(feature = "unit")]
+  use crate::{ArchiveBot, DateFormat, Discord, Locale};
+  #[cfg(feature = "unit")]
+  use crate::types::ChannelData;
+
+  /// Test localized messages and per-channel locale overrides.
+  #[test]
+  #[cfg(feature = "unit")]
+  fn test_locale() {
+    let data = vec![ChannelData {
+      id: "C01".to_string(),
+      name: "tiny".to_string(),
+      num_members: 1,
+      is_small: true,
+      is_member: true,
+      is_readable: true,
+      ..ChannelData::default()
+    }];
+    let bot = ArchiveBot {
+      notification_channel_id: "C_NOTIFY".to_string(),
+      locale: Locale::De,
+      channel_locales: vec![("C_JA", Locale::Ja)],
+      ..ArchiveBot::default()
+    };
+
+    let message = bot.create_message(bot.catalog("C_NOTIFY"), &data, None, 0);
+    assert!(message.contains("*Klein* (1)"));
+    assert!(message.contains("- <#C01> hat *1 Mitglied*. Keine aktuellen Nachrichten."));
+
+    let message = bot.create_message(bot.catalog("C_JA"), &data, None, 0);
+    assert!(message.contains("- <#C01> メンバーは*1人*です。"));
+
+    let message = bot.create_secondary_message(bot.catalog("C_NOTIFY"));
+    assert!(message.ends_with("Details findet ihr in <#C_NOTIFY>."));
+  }
+
+  /// Test Slack date tokens, fallback timezones and formats, and relative ages.
+  #[test]
+  #[cfg(feature = "unit")]
+  fn test_dates() {
+    // 2023-11-14 22:13:20 UTC, 43 days before `now`.
+    let ts = 1_700_000_000;
+    let now = ts + 43 * 24 * 60 * 60 + 60;
+    let data = vec![ChannelData {
+      id: "C01".to_string(),
+      name: "quiet".to_string(),
+      last_message_ts: ts,
+      last_message_relevant: true,
+      num_members: 10,
+      is_old: true,
+      is_member: true,
+      is_readable: true,
+      ..ChannelData::default()
+    }];
+    let message = |locale: Locale, dates: DateFormat| {
+      let bot = ArchiveBot { locale, dates, ..ArchiveBot::default() };
+      bot.create_message(bot.catalog(""), &data, None, now)
+    };
+
+    assert!(message(Locale::En, DateFormat::default())
+      .contains("The last message was on *<!date^1700000000^{date_short}|Nov 14, 2023 UTC>*."));
+    assert!(message(Locale::En, DateFormat {
+      token: "{date_long}",
+      timezone: chrono_tz::Europe::Berlin,
+      fallback_format: Some("%Y-%m-%d %H:%M %Z"),
+      ..DateFormat::default()
+    }).contains("<!date^1700000000^{date_long}|2023-11-14 23:13 CET>"));
+    assert!(message(Locale::En, DateFormat { fallback_format: Some("%Q"), ..DateFormat::default() })
+      .contains("<!date^1700000000^{date_short}|Nov 14, 2023 UTC>"));
+    assert!(message(Locale::En, DateFormat { token: "{ago}", ..DateFormat::default() })
+      .contains("The last message was *<!date^1700000000^{ago}|Nov 14, 2023 UTC>*."));
+    assert!(message(Locale::En, DateFormat { relative: true, ..DateFormat::default() })
+      .contains("The last message was *43 days ago*."));
+    assert!(message(Locale::De, DateFormat { relative: true, ..DateFormat::default() })
+      .contains("Die letzte Nachricht war *vor 43 Tagen*."));
+
+    // Out of range timestamps aren't dates.
+    let data = vec![ChannelData { last_message_ts: i64::MAX, ..data.into_iter().next().unwrap() }];
+    let bot = ArchiveBot::default();
+    assert!(bot.create_message(bot.catalog(""), &data, None, now).contains("The last message was on *[unable to parse timestamp]*."));
+  }
+
+  /// Test that private channels ask for `/invite` only on platforms that have it.
+  #[test]
+  #[cfg(feature = "unit")]
+  fn test_private_invite() {
+    let data = vec![ChannelData {
+      id: "C01".to_string(),
+      name: "secret".to_string(),
+      num_members: 2,
+      is_private: true,
+      ..ChannelData::default()
+    }];
+
+    let bot = ArchiveBot::default();
+    let message = bot.create_message(bot.catalog(""), &data, Some("archive-bot"), 0);
+    assert!(message.contains("run `/invite @archive-bot` in each:"));
+
+    let bot = ArchiveBot::default().with_platform(Discord::new("G"));
+    let c = bot.catalog("");
+    let message = bot.create_message(c, &data, Some("archive-bot"), 0);
+    assert!(message.contains(c.private_intro_permissions));
+    assert!(!message.contains("/invite"));
+  }
+}
@@ -0,0 +1,211 @@
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+use std::time::Duration;
+
+use crate::types::ChannelData;
+
+/// Metrics for the whole process.
+static METRICS: Metrics = Metrics::new();
+
+/// Run and API metrics, in Prometheus' text format.
+///
+/// Channel counts are from the latest run. API calls and errors are counted for Slack's Web API,
+/// by method and by `SlackError` variant.
+#[derive(Debug)]
+pub struct Metrics {
+  runs_ok: AtomicU64,
+  runs_failed: AtomicU64,
+  /// Unix time of the latest run, and of the latest successful run.
+  last_run: AtomicI64,
+  last_success: AtomicI64,
+  /// Duration of the latest run, in milliseconds.
+  run_duration_ms: AtomicU64,
+  channels_scanned: AtomicU64,
+  channels_stale: AtomicU64,
+  channels_small: AtomicU64,
+  channels_ignored: AtomicU64,
+  channels_unreadable: AtomicU64,
+  api_calls: Mutex<BTreeMap<String, u64>>,
+  api_errors: Mutex<BTreeMap<&'static str, u64>>,
+  rate_limit_waits: AtomicU64,
+  /// Total time spent waiting on rate limits, in milliseconds.
+  rate_limit_wait_ms: AtomicU64,
+}
+
+impl Metrics {
+  const fn new() -> Metrics {
+    Metrics {
+      runs_ok: AtomicU64::new(0),
+      runs_failed: AtomicU64::new(0),
+      last_run: AtomicI64::new(0),
+      last_success: AtomicI64::new(0),
+      run_duration_ms: AtomicU64::new(0),
+      channels_scanned: AtomicU64::new(0),
+      channels_stale: AtomicU64::new(0),
+      channels_small: AtomicU64::new(0),
+      channels_ignored: AtomicU64::new(0),
+      channels_unreadable: AtomicU64::new(0),
+      api_calls: Mutex::new(BTreeMap::new()),
+      api_errors: Mutex::new(BTreeMap::new()),
+      rate_limit_waits: AtomicU64::new(0),
+      rate_limit_wait_ms: AtomicU64::new(0),
+    }
+  }
+
+  /// Metrics for the whole process.
+  pub fn global() -> &'static Metrics {
+    &METRICS
+  }
+
+  /// Record a finished run, and the channels it scanned if it succeeded.
+  pub(crate) fn record_run(&self, duration: Duration, channels: Option<&[ChannelData]>) {
+    let now = chrono::offset::Utc::now().timestamp();
+    self.last_run.store(now, Ordering::Relaxed);
+    self.run_duration_ms.store(duration.as_millis() as u64, Ordering::Relaxed);
+    let channels = match channels {
+      Some(channels) => channels,
+      None => {
+        self.runs_failed.fetch_add(1, Ordering::Relaxed);
+        return;
+      },
+    };
+    self.runs_ok.fetch_add(1, Ordering::Relaxed);
+    self.last_success.store(now, Ordering::Relaxed);
+
+    let count = |flag: fn(&ChannelData) -> bool| channels.iter().filter(|c| !c.is_ignored && flag(c)).count() as u64;
+    self.channels_scanned.store(channels.len() as u64, Ordering::Relaxed);
+    self.channels_stale.store(count(|c| c.is_old), Ordering::Relaxed);
+    self.channels_small.store(count(|c| c.is_small), Ordering::Relaxed);
+    self.channels_unreadable.store(count(ChannelData::is_unreadable), Ordering::Relaxed);
+    self.channels_ignored.store(channels.iter().filter(|c| c.is_ignored).count() as u64, Ordering::Relaxed);
+  }
+
+  /// Record an API call, and its error if it failed.
+  pub(crate) fn record_api_call(&self, method: &str, error: Option<&'static str>) {
+    *self.api_calls.lock().unwrap().entry(method.to_string()).or_default() += 1;
+    if let Some(error) = error {
+      *self.api_errors.lock().unwrap().entry(error).or_default() += 1;
+    }
+  }
+
+  /// Record time spent waiting on a rate limit.
+  pub(crate) fn record_rate_limit_wait(&self, wait: Duration) {
+    self.rate_limit_waits.fetch_add(1, Ordering::Relaxed);
+    self.rate_limit_wait_ms.fetch_add(wait.as_millis() as u64, Ordering::Relaxed);
+  }
+
+  /// Render every metric in Prometheus' text exposition format.
+  pub fn render(&self) -> String {
+    let get = |value: &AtomicU64| value.load(Ordering::Relaxed);
+    let seconds = |value: &AtomicU64| value.load(Ordering::Relaxed) as f64 / 1000.0;
+    let mut out = String::new();
+
+    metric(&mut out, "archive_bot_runs_total", "counter", "Runs, by result.", &[
+      ("result=\"ok\"", get(&self.runs_ok).to_string()),
+      ("result=\"error\"", get(&self.runs_failed).to_string()),
+    ]);
+    metric(&mut out, "archive_bot_last_run_timestamp_seconds", "gauge", "When the latest run finished.", &[
+      ("", self.last_run.load(Ordering::Relaxed).to_string()),
+    ]);
+    metric(&mut out, "archive_bot_last_success_timestamp_seconds", "gauge", "When the latest successful run finished.", &[
+      ("", self.last_success.load(Ordering::Relaxed).to_string()),
+    ]);
+    metric(&mut out, "archive_bot_run_duration_seconds", "gauge", "How long the latest run took.", &[
+      ("", seconds(&self.run_duration_ms).to_string()),
+    ]);
+    metric(&mut out, "archive_bot_channels_scanned", "gauge", "Channels scanned in the latest successful run.", &[
+      ("", get(&self.channels_scanned).to_string()),
+    ]);
+    metric(&mut out, "archive_bot_channels", "gauge", "Channels in the latest successful run, by flag. Ignored channels only count as ignored.", &[
+      ("flag=\"stale\"", get(&self.channels_stale).to_string()),
+      ("flag=\"small\"", get(&self.channels_small).to_string()),
+      ("flag=\"ignored\"", get(&self.channels_ignored).to_string()),
+      ("flag=\"unreadable\"", get(&self.channels_unreadable).to_string()),
+    ]);
+
+    let calls: Vec<(String, String)> = self.api_calls.lock().unwrap().iter()
+      .map(|(method, n)| (format!("method=\"{}\"", method), n.to_string()))
+      .collect();
+    metric(&mut out, "archive_bot_api_calls_total", "counter", "Slack API calls, by method.", &calls);
+    let errors: Vec<(String, String)> = self.api_errors.lock().unwrap().iter()
+      .map(|(error, n)| (format!("error=\"{}\"", error), n.to_string()))
+      .collect();
+    metric(&mut out, "archive_bot_api_errors_total", "counter", "Slack API errors, by error.", &errors);
+
+    metric(&mut out, "archive_bot_rate_limit_waits_total", "counter", "Times a call waited on a rate limit.", &[
+      ("", get(&self.rate_limit_waits).to_string()),
+    ]);
+    metric(&mut out, "archive_bot_rate_limit_wait_seconds_total", "counter", "Time spent waiting on rate limits.", &[
+      ("", seconds(&self.rate_limit_wait_ms).to_string()),
+    ]);
+    out
+  }
+
+  /// Write every metric to a file, e.g. for node_exporter's textfile collector, or to push to a
+  /// Pushgateway. The file is replaced atomically, so it's never read half written.
+  pub fn write_textfile(&self, path: &Path) -> io::Result<()> {
+    let tmp = path.with_extension("prom.tmp");
+    fs::write(&tmp, self.render())?;
+    fs::rename(&tmp, path)
+  }
+}
+
+/// Append a metric with its help and type lines. Labels are written as `name="value"`.
+fn metric<L: AsRef<str>>(out: &mut String, name: &str, kind: &str, help: &str, samples: &[(L, String)]) {
+  let _ = writeln!(out, "# HELP {} {}", name, help);
+  let _ = writeln!(out, "# TYPE {} {}", name, kind);
+  for (labels, value) in samples {
+    match labels.as_ref() {
+      "" => { let _ = writeln!(out, "{} {}", name, value); },
+      labels => { let _ = writeln!(out, "{}{{{}}} {}", name, labels, value); },
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  #[cfg(feature = "unit")]
+  use super::*;
+
+  /// Test recording and rendering metrics.
+  #[test]
+  #[cfg(feature = "unit")]
+  fn test_metrics() {
+    let metrics = Metrics::new();
+    let channel = |is_old: bool, is_ignored: bool, is_readable: bool| ChannelData {
+      id: "C01".to_string(),
+      name: "channel".to_string(),
+      last_message_relevant: true,
+      num_members: 10,
+      is_old,
+      is_ignored,
+      is_private: !is_readable,
+      is_member: is_readable,
+      is_readable,
+      ..ChannelData::default()
+    };
+    let data = vec![channel(true, false, true), channel(true, true, true), channel(false, false, false)];
+    metrics.record_run(Duration::from_millis(1500), Some(&data));
+    metrics.record_run(Duration::from_millis(200), None);
+    metrics.record_api_call("conversations.list", None);
+    metrics.record_api_call("conversations.history", Some("NotInChannel"));
+    metrics.record_api_call("conversations.history", None);
+    metrics.record_rate_limit_wait(Duration::from_millis(250));
+
+    let text = metrics.render();
+    assert!(text.contains("# TYPE archive_bot_runs_total counter\narchive_bot_runs_total{result=\"ok\"} 1\narchive_bot_runs_total{result=\"error\"} 1\n"));
+    assert!(text.contains("archive_bot_run_duration_seconds 0.2\n"));
+    assert!(text.contains("archive_bot_channels_scanned 3\n"));
+    assert!(text.contains("archive_bot_channels{flag=\"stale\"} 1\n"));
+    assert!(text.contains("archive_bot_channels{flag=\"ignored\"} 1\n"));
+    assert!(text.contains("archive_bot_channels{flag=\"unreadable\"} 1\n"));
+    assert!(text.contains("archive_bot_api_calls_total{method=\"conversations.history\"} 2\n"));
+    assert!(text.contains("archive_bot_api_errors_total{error=\"NotInChannel\"} 1\n"));
+    assert!(text.contains("archive_bot_rate_limit_wait_seconds_total 0.25\n"));
+  }
+}
@@ -0,0 +1,118 @@
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing_core::span::Current;
+use tracing::{Event, Metadata, Subscriber};
+
+/// Serve canned responses by method and path, recording each request as "METHOD /path body".
+/// `prefix` is removed from paths before matching, e.g. an API version.
+pub(crate) async fn mock_server(prefix: &'static str, routes: Vec<(&'static str, u16, &'static str)>) -> (String, Arc<Mutex<Vec<String>>>) {
+  let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+  let url = format!("http://{}", listener.local_addr().unwrap());
+  let requests = Arc::new(Mutex::new(vec![]));
+  let log = requests.clone();
+  tokio::spawn(async move {
+    while let Ok((mut stream, _)) = listener.accept().await {
+      let mut buffer = vec![];
+      let mut chunk = [0; 4096];
+      // Read the headers, then as much body as they say there is.
+      let (head, body) = loop {
+        let n = stream.read(&mut chunk).await.unwrap();
+        buffer.extend_from_slice(&chunk[..n]);
+        let text = String::from_utf8_lossy(&buffer).to_string();
+        if let Some((head, body)) = text.split_once("\r\n\r\n") {
+          let length = head.lines()
+            .find_map(|l| l.to_lowercase().strip_prefix("content-length: ").map(|v| v.trim().parse::<usize>().unwrap()))
+            .unwrap_or(0);
+          if body.len() >= length || n == 0 {
+            break (head.to_string(), body.to_string());
+          }
+        }
+      };
+      let request_line = head.lines().next().unwrap();
+      let mut parts = request_line.split(' ');
+      let method = parts.next().unwrap();
+      let path = parts.next().unwrap().split('?').next().unwrap().trim_start_matches(prefix);
+      let route = format!("{} {}", method, path);
+      log.lock().unwrap().push(format!("{} {}", route, body).trim_end().to_string());
+
+      let (status, response) = routes.iter()
+        .find(|(r, _, _)| *r == route)
+        .map(|(_, status, response)| (*status, *response))
+        .unwrap_or((404, r#"{"message":"Not found"}"#));
+      let response = format!("HTTP/1.1 {} X\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}", status, response.len(), response);
+      stream.write_all(response.as_bytes()).await.unwrap();
+    }
+  });
+  (url, requests)
+}
+
+/// Record every span as its name and fields, tracking the entered span for `Span::current()`, e.g. "slack_api method=auth.test status=200".
+/// Install it with `tracing::subscriber::set_default(recorder.clone())`.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct SpanRecorder {
+  spans: Arc<Mutex<Vec<(&'static Metadata<'static>, String)>>>,
+  entered: Arc<Mutex<Vec<Id>>>,
+}
+
+impl SpanRecorder {
+  /// Every span with this name recorded so far.
+  pub(crate) fn spans(&self, name: &str) -> Vec<String> {
+    self.spans.lock().unwrap().iter()
+      .filter(|(metadata, _)| metadata.name() == name)
+      .map(|(_, line)| line.clone())
+      .collect()
+  }
+}
+
+/// Append each visited field to a span's line.
+struct FieldWriter<'a>(&'a mut String);
+
+impl Visit for FieldWriter<'_> {
+  fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+    self.0.push_str(&format!(" {}={:?}", field.name(), value));
+  }
+
+  fn record_str(&mut self, field: &Field, value: &str) {
+    self.0.push_str(&format!(" {}={}", field.name(), value));
+  }
+}
+
+impl Subscriber for SpanRecorder {
+  fn enabled(&self, _: &Metadata<'_>) -> bool {
+    true
+  }
+
+  fn new_span(&self, attributes: &Attributes<'_>) -> Id {
+    let mut spans = self.spans.lock().unwrap();
+    let mut line = attributes.metadata().name().to_string();
+    attributes.record(&mut FieldWriter(&mut line));
+    spans.push((attributes.metadata(), line));
+    Id::from_u64(spans.len() as u64)
+  }
+
+  fn record(&self, id: &Id, values: &Record<'_>) {
+    let mut spans = self.spans.lock().unwrap();
+    values.record(&mut FieldWriter(&mut spans[id.into_u64() as usize - 1].1));
+  }
+
+  fn record_follows_from(&self, _: &Id, _: &Id) {}
+  fn event(&self, _: &Event<'_>) {}
+  fn enter(&self, id: &Id) {
+    self.entered.lock().unwrap().push(id.clone());
+  }
+
+  fn exit(&self, _: &Id) {
+    self.entered.lock().unwrap().pop();
+  }
+
+  fn current_span(&self) -> Current {
+    match self.entered.lock().unwrap().last() {
+      Some(id) => Current::new(id.clone(), self.spans.lock().unwrap()[id.into_u64() as usize - 1].0),
+      None => Current::none(),
+    }
+  }
+}
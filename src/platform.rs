@@ -0,0 +1,94 @@
+use std::error::Error;
+use std::future::Future;
+
+use crate::types::{Channel, Message, User};
+
+/// The API calls Archive Bot makes to a chat platform.
+///
+/// Every call is given the bot's token to authenticate with. Ids are whatever the platform uses to
+/// identify channels, users and messages.
+pub trait ChatPlatform {
+  /// Error returned by failed API calls.
+  type Error: Error + 'static;
+
+  /// Longest message to post before splitting it into parts.
+  const MAX_MESSAGE_LENGTH: usize = crate::MAX_MESSAGE_LENGTH;
+
+  /// Whether every channel's activity can be read without joining it, e.g. with admin access.
+  /// If so, channels are never joined, and private channels are read too.
+  const READS_WITHOUT_JOINING: bool = false;
+
+  /// Whether members can add the bot to a private channel with `/invite @name`. If not, updates ask
+  /// for access to private channels instead.
+  const HAS_INVITE_COMMAND: bool = true;
+
+  /// Check a token's format, without making any API calls. Any token is accepted by default.
+  fn validate_token(&self, _token: &str) -> Result<(), String> {
+    Ok(())
+  }
+
+  /// List every channel that isn't archived, public and private.
+  fn channels(&self, token: &str) -> impl Future<Output = Result<Vec<Channel>, Self::Error>> + Send;
+
+  /// Get a channel's latest messages, newest first. `None` if the bot can't read the channel.
+  fn history(&self, token: &str, channel_id: &str, limit: u16) -> impl Future<Output = Result<Option<Vec<Message>>, Self::Error>> + Send;
+
+  /// List the ids of a channel's members.
+  fn members(&self, token: &str, channel_id: &str) -> impl Future<Output = Result<Vec<String>, Self::Error>> + Send;
+
+  /// List every user.
+  fn users(&self, token: &str) -> impl Future<Output = Result<Vec<User>, Self::Error>> + Send;
+
+  /// Get a single user.
+  fn user(&self, token: &str, user_id: &str) -> impl Future<Output = Result<User, Self::Error>> + Send;
+
+  /// Get the bot's own user name.
+  fn bot_name(&self, token: &str) -> impl Future<Output = Result<String, Self::Error>> + Send;
+
+  /// Join a public channel.
+  fn join(&self, token: &str, channel_id: &str) -> impl Future<Output = Result<(), Self::Error>> + Send;
+
+  /// Leave a channel.
+  fn leave(&self, token: &str, channel_id: &str) -> impl Future<Output = Result<(), Self::Error>> + Send;
+
+  /// Archive a channel.
+  fn archive(&self, token: &str, channel_id: &str) -> impl Future<Output = Result<(), Self::Error>> + Send;
+
+  /// Post a message, optionally as a reply in a thread. Returns the message's id, to reply to it.
+  fn post(&self, token: &str, channel_id: &str, thread_id: Option<&str>, text: &str) -> impl Future<Output = Result<String, Self::Error>> + Send;
+
+  /// Open a direct message conversation with a user. Returns the conversation's channel id.
+  fn open_direct_message(&self, token: &str, user_id: &str) -> impl Future<Output = Result<String, Self::Error>> + Send;
+
+  /// Upload a file and share it in a channel.
+  fn upload_file(&self, token: &str, channel_id: &str, filename: &str, content: &[u8]) -> impl Future<Output = Result<(), Self::Error>> + Send;
+}
+
+/// Translate the Slack markup in messages to another platform's Markdown. `*bold*` becomes `**bold**`.
+/// `channel` links to a channel by id, and `date` formats a timestamp given Slack's date token, such
+/// as `{date_short}`, and fallback text; if either returns `None`, the markup is left as-is.
+pub(crate) fn translate_markup(text: &str, channel: impl Fn(&str) -> Option<String>, date: impl Fn(i64, &str, &str) -> Option<String>) -> String {
+  let mut output = String::with_capacity(text.len());
+  let mut rest = text;
+  while let Some(start) = rest.find('<') {
+    output.push_str(&rest[..start].replace('*', "**"));
+    let tag = &rest[start..];
+    let Some(end) = tag.find('>') else {
+      break;
+    };
+    let (target, label) = tag[1..end].split_once('|').unwrap_or((&tag[1..end], ""));
+    let translated = match (target.strip_prefix('#'), target.strip_prefix("!date^")) {
+      (Some(id), _) => channel(id),
+      (_, Some(date_spec)) => {
+        let mut parts = date_spec.split('^');
+        let ts = parts.next().and_then(|t| t.parse().ok());
+        ts.and_then(|t| date(t, parts.next().unwrap_or_default(), label))
+      },
+      _ => None,
+    };
+    output.push_str(translated.as_deref().unwrap_or(&tag[..end + 1]));
+    rest = &tag[end + 1..];
+  }
+  output.push_str(&rest.replace('*', "**"));
+  output
+}
@@ -1,51 +1,163 @@
-use log::warn;
+use tracing::warn;
 
 use crate::ArchiveBot;
-use crate::types::*;
-use crate::error::SlackError;
-
-impl ArchiveBot {
-  /// Post a message to a channel.
-  pub async fn post_message(&self, channel_id: &str, message: &str) -> Result<SlackResponse, SlackError<reqwest::Error>> {
-    let mut params: UrlParams = vec![
-      ("channel", channel_id.to_string()),
-      ("text", message.to_string()),
-      ("mrkdwn", String::from("1")),
-    ];
-
-    match self.send("chat.postMessage", &mut params).await {
-      Ok(r) => Ok(r),
-      Err(e) => {
-        warn!("Unable to post message: {:}", e);
-        Err(e)
-      },
+use crate::audit::{AuditAction, REQUESTED};
+use crate::error::ArchiveError;
+use crate::platform::ChatPlatform;
+use crate::types::Channel;
+
+impl<P: ChatPlatform> ArchiveBot<P> {
+  /// Post a message to a channel. Returns the message's id.
+  pub async fn post_message(&self, channel_id: &str, message: &str) -> Result<String, P::Error> {
+    self.post(channel_id, message, None, REQUESTED).await
+  }
+
+  /// Post a reply in a message thread. Returns the reply's id.
+  pub async fn post_reply(&self, channel_id: &str, thread_id: &str, message: &str) -> Result<String, P::Error> {
+    self.post(channel_id, message, Some(thread_id), REQUESTED).await
+  }
+
+  /// Post a message, split into several at line boundaries if it's too long for one.
+  /// Stops at the first part that fails to post. Returns the id of each part.
+  pub async fn post_message_parts(&self, channel_id: &str, thread_id: Option<&str>, message: &str) -> Result<Vec<String>, P::Error> {
+    self.post_parts_because(channel_id, thread_id, message, REQUESTED).await
+  }
+
+  /// Post a message in parts, recording why in the audit log.
+  pub(crate) async fn post_parts_because(&self, channel_id: &str, thread_id: Option<&str>, message: &str, reason: &str) -> Result<Vec<String>, P::Error> {
+    let mut ids = vec![];
+    for part in split_message(message, P::MAX_MESSAGE_LENGTH) {
+      ids.push(self.post(channel_id, &part, thread_id, reason).await?);
     }
+    Ok(ids)
+  }
+
+  /// Post a message to a channel, optionally as a thread reply, recording why in the audit log.
+  pub(crate) async fn post(&self, channel_id: &str, message: &str, thread_id: Option<&str>, reason: &str) -> Result<String, P::Error> {
+    let result = self.platform.post(&self.token, channel_id, thread_id, message).await
+      .inspect_err(|e| warn!(channel_id, error = %e, "Unable to post message"));
+    self.audit(AuditAction::Post, channel_id, reason, &result);
+    result
+  }
+
+  /// Make Archive Bot leave a channel.
+  pub async fn leave_channel(&self, channel_id: &str) -> Result<(), P::Error> {
+    self.leave_channel_because(channel_id, REQUESTED).await
+  }
+
+  /// Leave a channel, recording why in the audit log.
+  pub(crate) async fn leave_channel_because(&self, channel_id: &str, reason: &str) -> Result<(), P::Error> {
+    let result = self.platform.leave(&self.token, channel_id).await
+      .inspect_err(|e| warn!(channel_id, error = %e, "Unable to leave channel"));
+    self.audit(AuditAction::Leave, channel_id, reason, &result);
+    result
+  }
+
+  /// Archive a channel, unless the shared channel policy doesn't allow it.
+  pub async fn archive_channel(&self, channel: &Channel) -> Result<(), ArchiveError<P::Error>> {
+    let channel_id = channel.id.as_str();
+    let result = match self.may_archive(channel) {
+      true => self.platform.archive(&self.token, channel_id).await.map_err(ArchiveError::Platform),
+      false => Err(ArchiveError::Shared),
+    }.inspect_err(|e| warn!(channel_id, error = %e, "Unable to archive channel"));
+    self.audit(AuditAction::Archive, channel_id, REQUESTED, &result);
+    result
+  }
+
+  /// Upload a file and share it in a channel.
+  pub async fn upload_file(&self, channel_id: &str, filename: &str, content: &[u8]) -> Result<(), P::Error> {
+    self.upload_file_because(channel_id, filename, content, REQUESTED).await
+  }
+
+  /// Upload a file, recording why in the audit log.
+  pub(crate) async fn upload_file_because(&self, channel_id: &str, filename: &str, content: &[u8], reason: &str) -> Result<(), P::Error> {
+    let result = self.platform.upload_file(&self.token, channel_id, filename, content).await
+      .inspect_err(|e| warn!(channel_id, filename, error = %e, "Unable to upload file"));
+    self.audit(AuditAction::Upload, channel_id, reason, &result);
+    result
+  }
+
+  /// Open a direct message conversation with a user. Returns the conversation's channel id.
+  pub async fn open_direct_message(&self, user_id: &str) -> Result<String, P::Error> {
+    self.platform.open_direct_message(&self.token, user_id).await
+      .inspect_err(|e| warn!(user_id, error = %e, "Unable to open direct message"))
   }
 
   /// Make Archive Bot join a channel.
-  pub async fn join_channel(&self, channel_id: &str) -> Result<SlackResponse, SlackError<reqwest::Error>> {
-    let mut params: UrlParams = vec![
-      ("channel", channel_id.to_string()),
-    ];
-
-    match self.send("conversations.join", &mut params).await {
-      Ok(r) => Ok(r),
-      Err(e) => {
-        warn!("Unable to join channel: {:}", e);
-        Err(e)
-      },
+  pub async fn join_channel(&self, channel_id: &str) -> Result<(), P::Error> {
+    self.join_channel_because(channel_id, REQUESTED).await
+  }
+
+  /// Join a channel, recording why in the audit log.
+  pub(crate) async fn join_channel_because(&self, channel_id: &str, reason: &str) -> Result<(), P::Error> {
+    let result = self.platform.join(&self.token, channel_id).await
+      .inspect_err(|e| warn!(channel_id, error = %e, "Unable to join channel"));
+    self.audit(AuditAction::Join, channel_id, reason, &result);
+    result
+  }
+}
+
+/// Split a message at line boundaries into parts no longer than `max_length`.
+/// Lines longer than a part are split mid-line.
+/// When split, parts are numbered "part 1/3" and only the first keeps the header (first line).
+fn split_message(message: &str, max_length: usize) -> Vec<String> {
+  if message.len() <= max_length {
+    return vec![message.to_string()];
+  }
+
+  // Leave room for the part label.
+  let limit = max_length.saturating_sub(20);
+  let mut parts: Vec<String> = vec![];
+  let mut part = String::new();
+  let limit = limit.max(1);
+  for mut line in message.lines() {
+    loop {
+      // Hard-split lines too long for any part, at a char boundary.
+      let mut end = line.len().min(limit);
+      while !line.is_char_boundary(end) {
+        end -= 1;
+      }
+      if end == 0 {
+        end = line.chars().next().map_or(0, char::len_utf8);
+      }
+      let (chunk, rest) = line.split_at(end);
+      if !part.is_empty() && part.len() + chunk.len() + 1 > limit {
+        parts.push(part);
+        part = String::new();
+      }
+      part.push_str(chunk);
+      part.push('\n');
+      if rest.is_empty() {
+        break;
+      }
+      line = rest;
     }
   }
+  if !part.is_empty() {
+    parts.push(part);
+  }
+
+  let total = parts.len();
+  parts.iter().enumerate().map(|(i, part)| {
+    let label = format!("_(part {}/{})_", i + 1, total);
+    match i {
+      0 => {
+        let (header, rest) = part.split_once('\n').unwrap_or((part, ""));
+        format!("{} {}\n{}", header, label, rest.trim_end())
+      },
+      _ => format!("{}\n{}", label, part.trim_end()),
+    }
+  }).collect()
 }
 
 #[cfg(test)]
 mod tests {
+  #[cfg(feature = "unit")]
+  use super::split_message;
   #[cfg(feature="unit_output")]
   use std::env;
   #[cfg(feature="unit_output")]
   use log::{info, error};
-  #[cfg(feature="unit_output")]
-  use simplelog;
 
   /// Create a test message and print it to stdout rather than posting to Slack.
   #[tokio::test]
@@ -60,4 +172,35 @@ mod tests {
       Err(e) => error!("Error: {}", e),
     }
   }
+
+  /// Test splitting long messages into numbered parts.
+  #[test]
+  #[cfg(feature = "unit")]
+  fn test_split_message() {
+    let message = "Header\n- one\n- two\n- three\n";
+    assert_eq!(split_message(message, 100), vec![message.to_string()]);
+
+    let lines: Vec<String> = (0..10).map(|i| format!("- <#C0000000{:02}> has 1 member.", i)).collect();
+    let message = format!("Header\n{}\n", lines.join("\n"));
+    let parts = split_message(&message, 100);
+    assert!(parts.len() > 1);
+    assert!(parts.iter().all(|p| p.len() <= 100));
+    assert!(parts[0].starts_with(&format!("Header _(part 1/{})_\n", parts.len())));
+    assert!(parts[1].starts_with(&format!("_(part 2/{})_\n- ", parts.len())));
+    assert_eq!(parts.iter().filter(|p| p.contains("Header")).count(), 1);
+    assert_eq!(parts.iter().map(|p| p.matches("- <#").count()).sum::<usize>(), 10);
+  }
+
+  /// Test that a single line longer than a part is split at a char boundary.
+  #[test]
+  #[cfg(feature = "unit")]
+  fn test_split_long_line() {
+    let line = "é".repeat(250);
+    let message = format!("Header\n{}\n", line);
+    let parts = split_message(&message, 100);
+    assert!(parts.len() > 5);
+    assert!(parts.iter().all(|p| p.len() <= 100));
+    let text: String = parts.iter().map(|p| p.split_once('\n').unwrap().1).collect();
+    assert_eq!(text, line);
+  }
 }
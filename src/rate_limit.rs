@@ -0,0 +1,110 @@
+use std::future::Future;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tokio::time::Instant;
+
+use crate::platform::ChatPlatform;
+use crate::types::{Channel, Message, User};
+
+/// A platform that makes at most so many API calls per minute, spaced evenly.
+/// Calls wait their turn rather than failing.
+#[derive(Debug)]
+pub struct RateLimited<P> {
+  /// Platform to make calls to.
+  pub platform: P,
+  /// Most calls to make per minute.
+  pub calls_per_minute: u32,
+  /// When the next call can be made.
+  next: Mutex<Instant>,
+}
+
+impl<P> RateLimited<P> {
+  /// Limit calls to a platform.
+  pub fn new(platform: P, calls_per_minute: u32) -> RateLimited<P> {
+    RateLimited {
+      platform,
+      calls_per_minute,
+      next: Mutex::new(Instant::now()),
+    }
+  }
+
+  /// Wait for the next call's turn.
+  async fn wait(&self) {
+    let interval = Duration::from_secs(60) / self.calls_per_minute.max(1);
+    let mut next = self.next.lock().await;
+    #[cfg(feature = "metrics")]
+    if let Some(wait) = next.checked_duration_since(Instant::now()) {
+      crate::Metrics::global().record_rate_limit_wait(wait);
+    }
+    tokio::time::sleep_until(*next).await;
+    *next = Instant::now().max(*next) + interval;
+  }
+
+  /// Make a call in turn.
+  async fn limit<T>(&self, call: impl Future<Output = T>) -> T {
+    self.wait().await;
+    call.await
+  }
+}
+
+impl<P: ChatPlatform + Sync> ChatPlatform for RateLimited<P> {
+  type Error = P::Error;
+
+  const MAX_MESSAGE_LENGTH: usize = P::MAX_MESSAGE_LENGTH;
+
+  const READS_WITHOUT_JOINING: bool = P::READS_WITHOUT_JOINING;
+
+  const HAS_INVITE_COMMAND: bool = P::HAS_INVITE_COMMAND;
+
+  fn validate_token(&self, token: &str) -> Result<(), String> {
+    self.platform.validate_token(token)
+  }
+
+  async fn channels(&self, token: &str) -> Result<Vec<Channel>, P::Error> {
+    self.limit(self.platform.channels(token)).await
+  }
+
+  async fn history(&self, token: &str, channel_id: &str, limit: u16) -> Result<Option<Vec<Message>>, P::Error> {
+    self.limit(self.platform.history(token, channel_id, limit)).await
+  }
+
+  async fn members(&self, token: &str, channel_id: &str) -> Result<Vec<String>, P::Error> {
+    self.limit(self.platform.members(token, channel_id)).await
+  }
+
+  async fn users(&self, token: &str) -> Result<Vec<User>, P::Error> {
+    self.limit(self.platform.users(token)).await
+  }
+
+  async fn user(&self, token: &str, user_id: &str) -> Result<User, P::Error> {
+    self.limit(self.platform.user(token, user_id)).await
+  }
+
+  async fn bot_name(&self, token: &str) -> Result<String, P::Error> {
+    self.limit(self.platform.bot_name(token)).await
+  }
+
+  async fn join(&self, token: &str, channel_id: &str) -> Result<(), P::Error> {
+    self.limit(self.platform.join(token, channel_id)).await
+  }
+
+  async fn leave(&self, token: &str, channel_id: &str) -> Result<(), P::Error> {
+    self.limit(self.platform.leave(token, channel_id)).await
+  }
+
+  async fn archive(&self, token: &str, channel_id: &str) -> Result<(), P::Error> {
+    self.limit(self.platform.archive(token, channel_id)).await
+  }
+
+  async fn post(&self, token: &str, channel_id: &str, thread_id: Option<&str>, text: &str) -> Result<String, P::Error> {
+    self.limit(self.platform.post(token, channel_id, thread_id, text)).await
+  }
+
+  async fn open_direct_message(&self, token: &str, user_id: &str) -> Result<String, P::Error> {
+    self.limit(self.platform.open_direct_message(token, user_id)).await
+  }
+
+  async fn upload_file(&self, token: &str, channel_id: &str, filename: &str, content: &[u8]) -> Result<(), P::Error> {
+    self.limit(self.platform.upload_file(token, channel_id, filename, content)).await
+  }
+}
@@ -0,0 +1,163 @@
+use crate::platform::ChatPlatform;
+use crate::types::*;
+use crate::error::SlackError;
+
+/// Slack's Web API. The default platform.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Slack;
+
+impl Slack {
+  /// Get every page of a paginated list.
+  async fn paginate<T>(&self, token: &str, method: &str, params: UrlParams<'_>, items: fn(SlackResponse) -> Vec<T>) -> Result<Vec<T>, SlackError<reqwest::Error>> {
+    let mut all: Vec<T> = vec![];
+    let mut cursor: String = "".to_string();
+    loop {
+      let mut page_params = params.clone();
+      page_params.push(("limit", String::from("1000")));
+      if !cursor.is_empty() {
+        page_params.push(("cursor", cursor));
+      }
+
+      let response = self.send(token, method, &mut page_params).await?;
+      cursor = response.response_metadata.as_ref().map(|m| m.next_cursor.clone()).unwrap_or_default();
+      all.extend(items(response));
+      if cursor.is_empty() {
+        break;
+      }
+    }
+    Ok(all)
+  }
+}
+
+impl ChatPlatform for Slack {
+  type Error = SlackError<reqwest::Error>;
+
+  fn validate_token(&self, token: &str) -> Result<(), String> {
+    match token.starts_with("xoxb-") || token.starts_with("xoxp-") {
+      true => Ok(()),
+      false => Err("token is not a bot or user token".to_string()),
+    }
+  }
+
+  async fn channels(&self, token: &str) -> Result<Vec<Channel>, Self::Error> {
+    let params: UrlParams = vec![
+      ("exclude_archived", String::from("1")),
+      ("exclude_members", String::from("0")),
+      ("types", String::from("public_channel,private_channel")),
+    ];
+    self.paginate(token, "conversations.list", params, |r| r.channels.unwrap_or_default()).await
+  }
+
+  async fn history(&self, token: &str, channel_id: &str, limit: u16) -> Result<Option<Vec<Message>>, Self::Error> {
+    let mut params: UrlParams = vec![
+      ("channel", channel_id.to_string()),
+      ("limit", limit.to_string()),
+    ];
+
+    match self.send(token, "conversations.history", &mut params).await {
+      Ok(response) => Ok(response.messages),
+      Err(SlackError::NotInChannel) => Ok(None),
+      Err(e) => Err(e),
+    }
+  }
+
+  async fn members(&self, token: &str, channel_id: &str) -> Result<Vec<String>, Self::Error> {
+    let params: UrlParams = vec![
+      ("channel", channel_id.to_string()),
+    ];
+    self.paginate(token, "conversations.members", params, |r| {
+      r.members.unwrap_or_default().iter().filter_map(StringOr::as_str).map(String::from).collect()
+    }).await
+  }
+
+  async fn users(&self, token: &str) -> Result<Vec<User>, Self::Error> {
+    self.paginate(token, "users.list", vec![], |r| {
+      r.members.unwrap_or_default().into_iter().filter_map(StringOr::into_object).collect()
+    }).await
+  }
+
+  async fn user(&self, token: &str, user_id: &str) -> Result<User, Self::Error> {
+    let mut params: UrlParams = vec![
+      ("user", user_id.to_string()),
+    ];
+
+    let response = self.send(token, "users.info", &mut params).await?;
+    response.user.and_then(StringOr::into_object).ok_or_else(|| SlackError::Unknown("missing_user".to_string()))
+  }
+
+  async fn bot_name(&self, token: &str) -> Result<String, Self::Error> {
+    let mut params: UrlParams = vec![];
+
+    let response = self.send(token, "auth.test", &mut params).await?;
+    response.user.as_ref().and_then(StringOr::as_str).map(String::from).ok_or_else(|| SlackError::Unknown("missing_user".to_string()))
+  }
+
+  async fn join(&self, token: &str, channel_id: &str) -> Result<(), Self::Error> {
+    let mut params: UrlParams = vec![
+      ("channel", channel_id.to_string()),
+    ];
+    self.send(token, "conversations.join", &mut params).await.map(|_| ())
+  }
+
+  async fn leave(&self, token: &str, channel_id: &str) -> Result<(), Self::Error> {
+    let mut params: UrlParams = vec![
+      ("channel", channel_id.to_string()),
+    ];
+    self.send(token, "conversations.leave", &mut params).await.map(|_| ())
+  }
+
+  async fn archive(&self, token: &str, channel_id: &str) -> Result<(), Self::Error> {
+    let mut params: UrlParams = vec![
+      ("channel", channel_id.to_string()),
+    ];
+    self.send(token, "conversations.archive", &mut params).await.map(|_| ())
+  }
+
+  async fn post(&self, token: &str, channel_id: &str, thread_id: Option<&str>, text: &str) -> Result<String, Self::Error> {
+    let mut params: UrlParams = vec![
+      ("channel", channel_id.to_string()),
+      ("text", text.to_string()),
+      ("mrkdwn", String::from("1")),
+    ];
+    if let Some(ts) = thread_id {
+      params.push(("thread_ts", ts.to_string()));
+    }
+
+    let response = self.send(token, "chat.postMessage", &mut params).await?;
+    response.ts.ok_or_else(|| SlackError::Unknown("missing_ts".to_string()))
+  }
+
+  async fn open_direct_message(&self, token: &str, user_id: &str) -> Result<String, Self::Error> {
+    let mut params: UrlParams = vec![
+      ("users", user_id.to_string()),
+    ];
+
+    let response = self.send(token, "conversations.open", &mut params).await?;
+    match response.channel.and_then(StringOr::into_object) {
+      Some(channel) => Ok(channel.id),
+      None => Err(SlackError::Unknown("missing_channel".to_string())),
+    }
+  }
+
+  async fn upload_file(&self, token: &str, channel_id: &str, filename: &str, content: &[u8]) -> Result<(), Self::Error> {
+    let mut params: UrlParams = vec![
+      ("filename", filename.to_string()),
+      ("length", content.len().to_string()),
+    ];
+    let (upload_url, file_id) = match self.send(token, "files.getUploadURLExternal", &mut params).await? {
+      SlackResponse { upload_url: Some(url), file_id: Some(id), .. } => (url, id),
+      _ => return Err(SlackError::Unknown("missing_upload_url".to_string())),
+    };
+
+    let client = reqwest::Client::new();
+    client.post(upload_url).body(content.to_vec()).send().await
+      .and_then(|r| r.error_for_status())
+      .map_err(SlackError::Client)?;
+
+    let mut params: UrlParams = vec![
+      ("files", serde_json::json!([{ "id": file_id, "title": filename }]).to_string()),
+      ("channel_id", channel_id.to_string()),
+    ];
+    self.send(token, "files.completeUploadExternal", &mut params).await.map(|_| ())
+  }
+}
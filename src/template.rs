@@ -0,0 +1,58 @@
+/// Default header template.
+pub const HEADER: &str = "{{header}}";
+/// Default template for each channel listed in an update.
+pub const LINE: &str = "<#{{channel_id}}> {{members}} {{activity}}";
+/// Default footer template.
+pub const FOOTER: &str = "";
+
+/// Template variables, in the format: ("name", "value").
+pub type Vars<'v> = Vec<(&'v str, String)>;
+
+/// Render a template, replacing `{{name}}` with the value of the variable `name`.
+/// Whitespace inside the braces is ignored. Unknown variables are left as-is.
+///
+/// Double braces are used so Slack's own date tokens, such as `{date_short}`, pass through.
+pub fn render(template: &str, vars: &Vars) -> String {
+  let mut output = String::with_capacity(template.len());
+  let mut rest = template;
+  while let Some(start) = rest.find("{{") {
+    output.push_str(&rest[..start]);
+    let tag = &rest[start..];
+    match tag.find("}}") {
+      Some(end) => {
+        let name = tag[2..end].trim();
+        match vars.iter().find(|(n, _)| *n == name) {
+          Some((_, value)) => output.push_str(value),
+          None => output.push_str(&tag[..end + 2]),
+        }
+        rest = &tag[end + 2..];
+      },
+      None => {
+        output.push_str(tag);
+        rest = "";
+      },
+    }
+  }
+  output.push_str(rest);
+  output
+}
+
+#[cfg(test)]
+mod tests {
+  #[cfg(feature = "unit")]
+  use super::*;
+
+  /// Test rendering template variables.
+  #[test]
+  #[cfg(feature = "unit")]
+  fn test_render() {
+    let vars: Vars = vec![
+      ("name", "general".to_string()),
+      ("num_members", "3".to_string()),
+    ];
+    assert_eq!(render("#{{name}} has {{ num_members }} members.", &vars), "#general has 3 members.");
+    assert_eq!(render("{{unknown}} {{name}}", &vars), "{{unknown}} general");
+    assert_eq!(render("<!date^0^{date_short}|x> {{name}", &vars), "<!date^0^{date_short}|x> {{name}");
+    assert_eq!(render("", &vars), "");
+  }
+}
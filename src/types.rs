@@ -1,10 +1,14 @@
-use chrono::NaiveDateTime;
+use chrono::{NaiveDateTime, TimeZone};
 use std::{error::Error, default::Default};
-use serde::Deserialize;
+use std::fmt::Write;
+use serde::{Deserialize, Serialize};
 use serde::de::{self, Deserializer};
 use serde_json::Value;
 
 use crate::error::SlackError;
+use crate::DateFormat;
+use crate::locale::Catalog;
+use crate::template;
 
 /// URL Parameters used to make requests.
 /// In the format: ("Header-Name", "Value").
@@ -13,20 +17,91 @@ pub type UrlParams<'sq> = Vec<(&'sq str, String)>;
 /// Possible json response from Slack API.
 #[derive(Clone, Debug, Deserialize)]
 pub struct SlackResponse {
+  pub channel: Option<StringOr<Channel>>,
   pub channels: Option<Vec<Channel>>,
   pub messages: Option<Vec<Message>>,
+  /// User ids from `conversations.members`, or users from `users.list`.
+  pub members: Option<Vec<StringOr<User>>>,
   pub url: Option<String>,
   pub team: Option<String>,
-  pub user: Option<String>,
+  pub user: Option<StringOr<User>>,
   pub team_id: Option<String>,
   pub user_id: Option<String>,
   pub bot_id: Option<String>,
+  /// Message timestamp; kept as a string as it's used verbatim as an id, e.g. for `thread_ts`.
+  pub ts: Option<String>,
   pub response_metadata: Option<SlackResponseMeta>,
+  /// Where to upload a file's contents, from `files.getUploadURLExternal`.
+  pub upload_url: Option<String>,
+  /// Id of a file being uploaded, from `files.getUploadURLExternal`.
+  pub file_id: Option<String>,
+  /// Channels from `admin.conversations.search`.
+  pub conversations: Option<Vec<AdminConversation>>,
+  /// Pagination marker for `admin.*` methods, which don't use `response_metadata`.
+  pub next_cursor: Option<String>,
   error: Option<String>,
   #[serde(default)]
   ok: bool,
 }
 
+/// Some keys hold a plain string (such as an id) for one method and a full object for another.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(untagged)]
+pub enum StringOr<T> {
+  String(String),
+  Object(T),
+}
+
+impl<T> StringOr<T> {
+  /// The plain string value, if that's what was returned.
+  pub fn as_str(&self) -> Option<&str> {
+    match self {
+      StringOr::String(s) => Some(s),
+      StringOr::Object(_) => None,
+    }
+  }
+
+  /// The full object, if that's what was returned.
+  pub fn into_object(self) -> Option<T> {
+    match self {
+      StringOr::String(_) => None,
+      StringOr::Object(o) => Some(o),
+    }
+  }
+}
+
+/// Channel from the `admin.conversations.*` methods, across an Enterprise Grid organization.
+/// https://api.slack.com/methods/admin.conversations.search
+#[derive(Clone, Debug, Deserialize)]
+pub struct AdminConversation {
+  pub id: String,
+  #[serde(default)]
+  pub name: String,
+  #[serde(default)]
+  pub member_count: i32,
+  /// Seconds or milliseconds since the epoch; see `normalize_timestamp`.
+  #[serde(default)]
+  pub created: i64,
+  pub creator_id: Option<String>,
+  #[serde(default)]
+  pub is_private: bool,
+  #[serde(default)]
+  pub is_general: bool,
+  #[serde(default)]
+  pub is_ext_shared: bool,
+  #[serde(default)]
+  pub is_org_shared: bool,
+  #[serde(default)]
+  pub is_pending_ext_shared: bool,
+  /// Seconds, milliseconds or microseconds since the epoch; see `normalize_timestamp`.
+  #[serde(default)]
+  pub last_activity_ts: i64,
+  /// Workspace that hosts an org-shared channel.
+  pub conversation_host_id: Option<String>,
+  /// Workspace the channel was found in.
+  pub context_team_id: Option<String>,
+}
+
 /// Metadata including next cursor (pagination marker).
 #[derive(Clone, Debug, Deserialize)]
 pub struct SlackResponseMeta {
@@ -34,98 +109,161 @@ pub struct SlackResponseMeta {
   pub next_cursor: String,
 }
 
-impl<E: Error> Into<Result<SlackResponse, SlackError<E>>> for SlackResponse {
+impl<E: Error> From<SlackResponse> for Result<SlackResponse, SlackError<E>> {
   /// Parse response.
-  fn into(self) -> Result<SlackResponse, SlackError<E>> {
-    if self.ok {
-      Ok(self)
+  fn from(response: SlackResponse) -> Self {
+    if response.ok {
+      Ok(response)
     } else {
-      Err(self.error.as_ref().map(String::as_ref).unwrap_or("").into())
+      Err(response.error.as_deref().unwrap_or("").into())
     }
   }
 }
 
 /// Conversation response. Non-comprehensive.
 /// https://api.slack.com/types/conversation
-#[derive(Clone, Debug, Deserialize)]
+#[derive(Clone, Debug, Default, Deserialize)]
 pub struct Channel {
+  /// Channel id.
   pub id: String,
+  /// Channel name, without the leading `#`.
   #[serde(default)]
   pub name: String,
+  /// Whether this is a public channel.
   #[serde(default = "default_true")]
   #[serde(deserialize_with = "boolean_truthy")]
   pub is_channel: bool,
+  /// Whether this is a private channel (legacy).
   #[serde(default)]
   #[serde(deserialize_with = "boolean_truthy")]
   pub is_group: bool,
+  /// Whether this is a direct message.
   #[serde(default)]
   #[serde(deserialize_with = "boolean_truthy")]
   pub is_im: bool,
+  /// When the channel was created.
   pub created: Option<Timestamp>,
+  /// User id of the channel's creator.
   pub creator: Option<String>,
+  /// Whether the channel is archived.
   #[serde(default)]
   #[serde(deserialize_with = "boolean_truthy")]
   pub is_archived: bool,
+  /// Whether this is the workspace's general channel.
   #[serde(default)]
   #[serde(deserialize_with = "boolean_truthy")]
   pub is_general: bool,
+  /// Unused.
   #[serde(default)]
   #[serde(deserialize_with = "boolean_truthy")]
   pub unlinked: bool,
+  /// Channel name, normalized.
   pub name_normalized: Option<String>,
+  /// Whether the channel is read-only.
   #[serde(default)]
   #[serde(deserialize_with = "boolean_truthy")]
   pub is_read_only: bool,
+  /// Whether the channel is shared with another workspace.
   #[serde(default)]
   #[serde(deserialize_with = "boolean_truthy")]
   pub is_shared: bool,
+  /// Whether the channel is shared with another organization.
   #[serde(default)]
   #[serde(deserialize_with = "boolean_truthy")]
   pub is_ext_shared: bool,
+  /// Whether the channel is shared across an organization.
   #[serde(default)]
   #[serde(deserialize_with = "boolean_truthy")]
   pub is_org_shared: bool,
+  /// Workspaces the channel is pending being shared with.
   #[serde(default)]
   pub pending_shared: Option<Vec<String>>,
+  /// Whether the channel is pending being shared with another organization.
   #[serde(default)]
   #[serde(deserialize_with = "boolean_truthy")]
   pub is_pending_ext_shared: bool,
+  /// Whether the bot is a member of the channel.
   #[serde(default)]
   #[serde(deserialize_with = "boolean_truthy")]
   pub is_member: bool,
+  /// Whether the channel is private.
   #[serde(default)]
   #[serde(deserialize_with = "boolean_truthy")]
   pub is_private: bool,
+  /// Whether this is a multi-person direct message.
   #[serde(default)]
   #[serde(deserialize_with = "boolean_truthy")]
   pub is_mpim: bool,
+  /// Timestamp of the last message the bot read.
   pub last_read: Option<String>,
   // topic
   // purpose
+  /// Previous names of the channel.
   pub previous_names: Option<Vec<String>>,
+  /// Number of members.
   #[serde(default)]
   pub num_members: i32,
+  /// Id of the workspace the channel belongs to, on Enterprise Grid.
+  #[serde(alias = "context_team_id")]
+  pub team_id: Option<String>,
+}
+
+/// User response. Non-comprehensive.
+/// https://api.slack.com/types/user
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct User {
+  /// User id.
+  pub id: String,
+  /// User name.
+  #[serde(default)]
+  pub name: String,
+  /// Whether the user is deactivated.
+  #[serde(default)]
+  #[serde(deserialize_with = "boolean_truthy")]
+  pub deleted: bool,
+  /// Whether the user is a bot.
+  #[serde(default)]
+  #[serde(deserialize_with = "boolean_truthy")]
+  pub is_bot: bool,
+  /// Whether the user is a guest, including single-channel guests.
+  #[serde(default)]
+  #[serde(rename = "is_restricted")]
+  #[serde(deserialize_with = "boolean_truthy")]
+  pub is_guest: bool,
+}
+
+impl User {
+  /// Whether the user is an active member of the workspace, as opposed to a bot, guest or deactivated account.
+  pub fn is_active_human(&self) -> bool {
+    !self.deleted && !self.is_bot && !self.is_guest && self.id != "USLACKBOT"
+  }
 }
 
 /// Message data response. Non-comprehensive.
 /// https://api.slack.com/events/message
-#[derive(Clone, Debug, Deserialize)]
+#[derive(Clone, Debug, Default, Deserialize)]
 pub struct Message {
+  /// Event type; `message` for messages.
   #[serde(rename = "type")]
   #[serde(default)]
   pub event_type: String,
+  /// Message subtype, e.g. `channel_join`.
   #[serde(default)]
   pub subtype: String,
+  /// Channel id.
   pub channel: Option<String>,
+  /// User id of the author.
   pub user: Option<String>,
+  /// Message text.
   pub text: Option<String>,
+  /// When the message was posted.
   pub ts: Option<Timestamp>,
   // edited
 }
 
 /// Treat these event message subtypes as irrelevant to channel activity.
 /// see https://api.slack.com/events/message#subtypes
-const IGNORED_MESSAGE_TYPES: [&'static str; 15] = [
+const IGNORED_MESSAGE_TYPES: [&str; 15] = [
   "bot_add",
   "bot_remove",
   "bot_message",
@@ -159,10 +297,10 @@ impl Message {
 fn boolean_truthy<'de, D: Deserializer<'de>>(deserializer: D) -> Result<bool, D::Error> {
   match Value::deserialize(deserializer)? {
     Value::Bool(b) => Ok(b),
-    Value::String(s) => Ok(s != "0" && s != "" && s != "false" && s != "FALSE"),
+    Value::String(s) => Ok(s != "0" && !s.is_empty() && s != "false" && s != "FALSE"),
     Value::Number(num) => Ok(num.as_i64().ok_or(de::Error::custom("Invalid number"))? != 0),
     Value::Null => Ok(false),
-    _ => return Err(de::Error::custom("Wrong type, expected boolean")),
+    _ => Err(de::Error::custom("Wrong type, expected boolean")),
   }
 }
 
@@ -175,6 +313,7 @@ const fn default_true() -> bool { true }
 pub struct Timestamp(i64);
 
 impl Timestamp {
+  /// Timestamp from seconds since the epoch.
   pub fn new(t: i64) -> Timestamp {
     Timestamp(t)
   }
@@ -228,31 +367,139 @@ impl<'de> ::serde::Deserialize<'de> for Timestamp {
 
     Err(D::Error::custom(format!(
       "expected a timestamp but got: {}",
-      value.to_string()
+      value
     )))
   }
 }
 
+/// Sections of an update, in the order they're listed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Section {
+  Stale,
+  Small,
+  StaleAndSmall,
+  Shared,
+  PendingShared,
+  Private,
+}
+
+impl Section {
+  /// Section heading.
+  pub fn title(&self, c: &Catalog) -> &'static str {
+    match self {
+      Section::Stale => c.section_stale,
+      Section::Small => c.section_small,
+      Section::StaleAndSmall => c.section_stale_and_small,
+      Section::Shared => c.section_shared,
+      Section::PendingShared => c.section_pending_shared,
+      Section::Private => c.section_private,
+    }
+  }
+}
+
 /// Parsed channel data.
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Default, PartialEq, Serialize)]
 pub struct ChannelData {
+  /// Channel id.
   pub id: String,
+  /// Channel name, without the leading `#`.
   pub name: String,
+  /// Timestamp of the last message or event, or 0 if there's none.
   pub last_message_ts: i64,
+  /// Whether the last message was an actual message, rather than an event like someone joining.
   pub last_message_relevant: bool,
+  /// Number of members, not counting the bot.
   pub num_members: i32,
+  /// Timestamp of when the channel was created, or 0 if unknown.
+  pub created: i64,
+  /// Whether the channel hasn't been active recently.
   pub is_old: bool,
+  /// Whether the channel has few members.
   pub is_small: bool,
+  /// Whether the channel is too new to be reported.
+  pub is_new: bool,
+  /// Whether the channel is filtered out by prefix.
   pub is_ignored: bool,
+  /// Whether the channel is private.
   pub is_private: bool,
+  /// Whether the channel is shared with another organization.
+  pub is_ext_shared: bool,
+  /// Whether the channel has an invitation to another organization that hasn't been accepted.
+  pub is_pending_ext_shared: bool,
+  /// Whether the bot is a member of the channel.
+  pub is_member: bool,
+  /// Whether the channel's history could be read. Public channels the bot didn't join may not be.
+  pub is_readable: bool,
+  /// Whether reading the channel's history failed, e.g. because of an API error.
+  pub history_failed: bool,
+  /// Whether the bot joined the channel during this run (or in a dry run, would have).
+  pub joined: bool,
+  /// Whether the bot tried to join the channel during this run, and couldn't.
+  pub join_failed: bool,
+  /// Whether the bot left the channel again after joining it (or in a dry run, would have).
+  pub left: bool,
+  /// User id of the channel's creator.
+  pub creator: Option<String>,
+  /// Id of the workspace the channel belongs to, on Enterprise Grid.
+  pub team_id: Option<String>,
 }
 
 impl ChannelData {
+  /// Timestamp of the latest known activity: the last message, or else when the channel was created.
+  pub fn last_activity(&self) -> i64 {
+    match self.last_message_ts {
+      0 => self.created,
+      ts => ts,
+    }
+  }
+
+  /// Whether the channel is private and the bot hasn't been invited, so its history can't be read.
+  pub fn is_unreadable(&self) -> bool {
+    self.is_private && !self.is_member && !self.is_readable
+  }
+
+  /// Whether the channel's history wasn't read for another reason, e.g. it's public and the bot
+  /// didn't join it, or this is a dry run. Such channels are only judged by their size.
+  pub fn is_history_unknown(&self) -> bool {
+    !self.is_readable && !self.is_unreadable() && !self.history_failed
+  }
+
   /// Format timestamp to human date.
-  pub fn last_message_ts_formatted(&self) -> String {
-    let t: i64 = self.last_message_ts;
-    if t == 0 { return "[unable to parse timestamp]".to_string() }
-    let fallback = NaiveDateTime::from_timestamp_opt(t, 0).unwrap().format("%b %d, %Y UTC");
-    format!("<!date^{}^{{date_short}}|{}>", t, fallback)
+  pub(crate) fn last_message_ts_formatted(&self, c: &Catalog, dates: &DateFormat, now: i64) -> String {
+    dates.format(c, self.last_message_ts, now)
+  }
+
+  /// Format creation date to human date.
+  pub(crate) fn created_formatted(&self, c: &Catalog, dates: &DateFormat, now: i64) -> String {
+    dates.format(c, self.created, now)
+  }
+}
+
+impl DateFormat {
+  /// Whether dates read as how long ago, e.g. "43 days ago", rather than as a date.
+  pub(crate) fn is_relative(&self) -> bool {
+    self.relative || self.token == "{ago}"
+  }
+
+  /// Format a unix timestamp as a Slack date, or as how long before `now` it was.
+  fn format(&self, c: &Catalog, t: i64, now: i64) -> String {
+    if t == 0 { return c.unparsable_timestamp.to_string() }
+    if self.relative {
+      return match (now - t).max(0) / (24 * 60 * 60) {
+        0 => c.today.to_string(),
+        days => template::render(c.plural(&c.days_ago, days), &vec![("count", days.to_string())]),
+      };
+    }
+    let date = match NaiveDateTime::from_timestamp_opt(t, 0) {
+      Some(date) => self.timezone.from_utc_datetime(&date),
+      None => return c.unparsable_timestamp.to_string(),
+    };
+    let mut fallback = String::new();
+    // An invalid format fails to display, rather than failing up front; use the locale's instead.
+    if write!(fallback, "{}", date.format(self.fallback_format.unwrap_or(c.date_format))).is_err() {
+      fallback.clear();
+      let _ = write!(fallback, "{}", date.format(c.date_format));
+    }
+    format!("<!date^{}^{}|{}>", t, self.token, fallback)
   }
 }
@@ -0,0 +1,224 @@
+use std::collections::HashSet;
+use std::error::Error;
+use futures::future;
+use tracing::{info, info_span, warn, Instrument};
+
+use crate::{Action, ArchiveBot, RunReport};
+use crate::platform::ChatPlatform;
+use crate::slack::Slack;
+use crate::template::{self, Vars};
+
+/// A workspace to run Archive Bot in, with its own token and notification channel.
+#[derive(Debug)]
+pub struct Workspace<P = Slack> {
+  /// Name of the workspace, for logs and the combined summary.
+  pub name: &'static str,
+  /// Bot configuration for the workspace. To limit the rate of API calls to the workspace, use a
+  /// `RateLimited` platform.
+  pub bot: ArchiveBot<P>,
+}
+
+/// Where to post a summary across every workspace.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CombinedSummary {
+  /// Name of the workspace to post in.
+  pub workspace: &'static str,
+  /// Channel to post in.
+  pub channel_id: String,
+}
+
+/// Run Archive Bot in several workspaces at once.
+#[derive(Debug)]
+pub struct Workspaces<P = Slack> {
+  /// Workspaces to run in.
+  pub workspaces: Vec<Workspace<P>>,
+  /// Post a summary across every workspace once they've all run. Not posted if `None`.
+  pub summary: Option<CombinedSummary>,
+}
+
+/// Outcome of a run in one workspace.
+#[derive(Debug)]
+pub struct WorkspaceReport {
+  /// Name of the workspace.
+  pub name: &'static str,
+  /// Outcome of the run, or why it failed.
+  pub result: Result<RunReport, Box<dyn Error>>,
+}
+
+/// Outcome of a run in every workspace.
+#[derive(Debug, Default)]
+pub struct WorkspacesReport {
+  /// Outcome in each workspace, in order.
+  pub workspaces: Vec<WorkspaceReport>,
+  /// Combined summary, if configured. In a dry run of the summary's workspace, what would have been posted.
+  pub summary: Option<Action>,
+}
+
+impl<P: ChatPlatform> Workspaces<P> {
+  /// Check the configuration of every workspace for problems. Doesn't make any API calls.
+  pub fn validate(&self) -> Vec<String> {
+    let mut errors = vec![];
+    let mut names = HashSet::new();
+    for workspace in &self.workspaces {
+      if !names.insert(workspace.name) {
+        errors.push(format!("workspace \"{}\" is configured more than once", workspace.name));
+      }
+      errors.extend(workspace.bot.validate().into_iter().map(|e| format!("{}: {}", workspace.name, e)));
+    }
+    if let Some(summary) = &self.summary {
+      if !names.contains(summary.workspace) {
+        errors.push(format!("summary workspace \"{}\" is not configured", summary.workspace));
+      }
+      if summary.channel_id.is_empty() {
+        errors.push("summary channel_id is not set".to_string());
+      }
+    }
+    errors
+  }
+
+  /// Run the bot in every workspace concurrently, then post the combined summary.
+  /// A failed run in one workspace doesn't stop the others.
+  pub async fn run(&self) -> WorkspacesReport {
+    let results = future::join_all(self.workspaces.iter().map(|workspace| async move {
+      info!("Running in workspace");
+      let result = workspace.bot.run().await;
+      if let Err(e) = &result {
+        warn!(error = %e, "Run failed in workspace");
+      }
+      WorkspaceReport { name: workspace.name, result }
+    }.instrument(info_span!("workspace", name = workspace.name)))).await;
+
+    let summary = self.summary.as_ref().and_then(|summary| {
+      let workspace = self.workspaces.iter().find(|w| w.name == summary.workspace)?;
+      Some((workspace, self.create_summary(&workspace.bot, &summary.channel_id, &results)))
+    });
+    let summary = match summary {
+      Some((workspace, action)) => {
+        if !workspace.bot.dry_run && workspace.bot.perform(&action, "combined summary").await {
+          info!(workspace = workspace.name, "Posted combined summary");
+        }
+        Some(action)
+      },
+      None => None,
+    };
+
+    WorkspacesReport { workspaces: results, summary }
+  }
+
+  /// Create a summary of every workspace's results, in the language of the channel it's posted in.
+  /// Each workspace's channels are counted by its own configuration.
+  fn create_summary(&self, bot: &ArchiveBot<P>, channel_id: &str, results: &[WorkspaceReport]) -> Action {
+    let c = bot.catalog(channel_id);
+    let mut total = 0;
+    let mut lines: Vec<String> = vec![];
+    for (workspace, report) in self.workspaces.iter().zip(results) {
+      let line = match &report.result {
+        Ok(report) => {
+          let (count, sections) = workspace.bot.section_counts(c, &report.channels);
+          total += count;
+          let vars: Vars = vec![
+            ("name", workspace.name.to_string()),
+            ("count", count.to_string()),
+            ("sections", sections),
+          ];
+          match count {
+            0 => template::render(c.workspace_clean, &vars),
+            _ => template::render(c.plural(&c.workspace_line, count as i64), &vars),
+          }
+        },
+        Err(_) => template::render(c.workspace_failed, &vec![("name", workspace.name.to_string())]),
+      };
+      lines.push(format!("- {}", line));
+    }
+
+    let vars: Vars = vec![
+      ("count", total.to_string()),
+      ("workspaces", self.workspaces.len().to_string()),
+    ];
+    Action::Post {
+      channel_id: channel_id.to_string(),
+      text: format!("{}\n{}", template::render(c.plural(&c.workspaces_summary, total as i64), &vars), lines.join("\n")),
+      replies: vec![],
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  #[cfg(feature = "unit")]
+  use super::*;
+  #[cfg(feature = "unit")]
+  use std::collections::HashMap;
+  #[cfg(feature = "unit")]
+  use crate::{Channel, FakeCall, FakePlatform, Message, RateLimited, Timestamp};
+
+  /// Test running in several workspaces, with a combined summary.
+  #[tokio::test]
+  #[cfg(feature = "unit")]
+  async fn test_workspaces() {
+    let now = chrono::offset::Utc::now().timestamp();
+    let workspace = |name: &'static str, channels: &[(&str, i64)], failing: Vec<&'static str>| {
+      let mut platform = FakePlatform::default();
+      platform.channels = channels.iter().map(|(id, _)| Channel {
+        id: id.to_string(),
+        name: id.to_lowercase(),
+        is_channel: true,
+        is_member: true,
+        num_members: 10,
+        created: Some(Timestamp::new(now - 365 * 24 * 60 * 60)),
+        ..Channel::default()
+      }).collect();
+      platform.histories = channels.iter().map(|(id, days)| (id.to_string(), vec![Message {
+        event_type: "message".to_string(),
+        ts: Some(Timestamp::new(now - days * 24 * 60 * 60)),
+        ..Message::default()
+      }])).collect::<HashMap<_, _>>();
+      platform.failing = failing;
+      Workspace {
+        name,
+        bot: ArchiveBot {
+          token: "xoxb-token".to_string(),
+          notification_channel_id: format!("{}-updates", name),
+          ..ArchiveBot::default()
+        }.with_platform(RateLimited::new(platform, 6000)),
+      }
+    };
+
+    let workspaces = Workspaces {
+      workspaces: vec![
+        workspace("acme", &[("C01", 100), ("C02", 1)], vec![]),
+        workspace("widgets", &[("C03", 1)], vec![]),
+        workspace("broken", &[], vec!["channels"]),
+      ],
+      summary: Some(CombinedSummary { workspace: "acme", channel_id: "C_ALL".to_string() }),
+    };
+    assert!(workspaces.validate().is_empty());
+
+    let report = workspaces.run().await;
+    assert_eq!(report.workspaces.iter().map(|w| w.name).collect::<Vec<_>>(), vec!["acme", "widgets", "broken"]);
+    assert_eq!(report.workspaces[0].result.as_ref().unwrap().channels.len(), 2);
+    assert!(report.workspaces[2].result.is_err());
+
+    let calls = workspaces.workspaces[0].bot.platform.platform.calls();
+    match calls.last() {
+      Some(FakeCall::Post { channel_id, text, .. }) => {
+        assert_eq!(channel_id, "C_ALL");
+        assert_eq!(text, "Across 3 workspaces, I found 1 channel to look at:\n- *acme*: 1 channel: Stale (1).\n- *widgets*: nothing to look at.\n- *broken*: I couldn't check this workspace.");
+      },
+      call => panic!("unexpected call: {:?}", call),
+    }
+    assert!(workspaces.workspaces[1].bot.platform.platform.calls().is_empty());
+  }
+
+  /// Test that calls are spaced out.
+  #[tokio::test]
+  #[cfg(feature = "unit")]
+  async fn test_rate_limit() {
+    let platform = RateLimited::new(FakePlatform::default(), 600);
+    let start = tokio::time::Instant::now();
+    for _ in 0..3 {
+      platform.channels("token").await.unwrap();
+    }
+    assert!(start.elapsed() >= std::time::Duration::from_millis(200));
+  }
+}